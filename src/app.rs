@@ -1,84 +1,98 @@
-use std::{io, thread, time::{Duration, Instant}};
-use tui::{
-    terminal::Terminal,
+use std::{io, time::{Duration, Instant}};
+use ratatui::{
+    Terminal,
     backend::CrosstermBackend,
-    layout::Alignment,
-    widgets::{Borders, Block, Paragraph},
-    style::{Color, Style, Modifier},
-    text::{Span, Spans},
 };
 use crossterm::{
     execute,
     event,
-    event::Event,
-    event::KeyCode,
-    terminal::{enable_raw_mode, disable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen, SetSize, ScrollUp}
+    event::{DisableFocusChange, EnableFocusChange, Event, KeyEventKind, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags},
+    terminal::{enable_raw_mode, disable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen}
 };
 
-use crate::screen;
+use crate::{args::Args, keys::Action, screen};
+
+// frames simulated at most before drawing again; the rest are dropped
+const MAX_CATCH_UP: u32 = 10;
 
 pub struct App {
     terminal: Terminal<CrosstermBackend<io::Stdout>>,
-    screen: screen::Screen
+    screen: screen::Screen,
+    /// Whether key releases were turned on and have to be turned off again.
+    releases: bool
 }
 
 impl App {
-    pub fn new() -> Result<App, io::Error> {
-        enable_raw_mode().unwrap();
+    pub fn new(args: &Args) -> Result<App, io::Error> {
+        enable_raw_mode()?;
         let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen).unwrap();
+        execute!(stdout, EnterAlternateScreen, EnableFocusChange)?;
+        // key releases where the terminal can report them
+        let releases = supports_keyboard_enhancement().unwrap_or(false);
+        if releases {
+            execute!(stdout, PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES))?;
+        }
         let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend).unwrap();
-        terminal.hide_cursor().unwrap();
+        let mut terminal = Terminal::new(backend)?;
+        terminal.hide_cursor()?;
 
+        let mut screen = screen::Screen::new(args);
+        screen.controller.releases = releases;
         Ok(App {
             terminal,
-            screen: screen::Screen::new()
+            screen,
+            releases
         })
     }
 
     pub fn run(&mut self) -> io::Result<()> {
-        let tick_rate = Duration::from_millis(100);
-        let mut last_tick = Instant::now();
-        let mut loop_count = 0u32;
+        let tick = tetris::game::TICK;
+        let mut last_frame = Instant::now();
+        // real time not simulated yet
+        let mut lag = Duration::ZERO;
         loop {
-            // tick
-            if last_tick.elapsed() >= tick_rate {
-                self.screen.on_tick();
-                last_tick = Instant::now();
-            }
-
-            // keydown
-            let timeout = tick_rate
-                .checked_sub(last_tick.elapsed())
-                .unwrap_or_else(|| Duration::from_secs(0));
-            if crossterm::event::poll(timeout)? {
-                if let Event::Key(key) = event::read()? {
-                    if let KeyCode::Char('q') = key.code {
-                        return Ok(());
-                    } else {
-                        self.screen.on_keydown(&key);
+            // keydown, waiting until the next frame is due
+            if crossterm::event::poll(tick.saturating_sub(lag + last_frame.elapsed()))? {
+                // every pending key, so fast taps are not lost
+                loop {
+                    match event::read()? {
+                        Event::Key(key) if key.kind == KeyEventKind::Release => {
+                            self.screen.on_keyup(&key);
+                        },
+                        Event::Key(key) => {
+                            if self.screen.keys.action(&key) == Some(Action::Quit) {
+                                return Ok(());
+                            }
+                            self.screen.on_keydown(&key);
+                        },
+                        Event::FocusLost => self.screen.on_focus_lost(),
+                        _ => {}
                     }
-
-                    // consume remaining key events
-                    while crossterm::event::poll(Duration::from_secs(0))? {
-                        event::read()?;
+                    if !crossterm::event::poll(Duration::ZERO)? {
+                        break;
                     }
                 }
             }
 
-            // draw
-            self.terminal.draw(
-                |frame| { self.screen.draw(frame) }
-            ).unwrap();
-
-            // consume time
-            if let Some(dur) = tick_rate.checked_sub(last_tick.elapsed()) {
-                thread::sleep(dur);
+            // fixed step, catching up on frames missed while drawing
+            let now = Instant::now();
+            lag += now - last_frame;
+            last_frame = now;
+            let mut steps = 0;
+            while lag >= tick {
+                lag -= tick;
+                if steps < MAX_CATCH_UP {
+                    self.screen.on_tick();
+                    steps += 1;
+                }
             }
 
-            loop_count += 1;
-            // self.screen.debug_msg = format!("{}", count);
+            // draw
+            if steps > 0 {
+                self.terminal.draw(
+                    |frame| { self.screen.draw(frame) }
+                )?;
+            }
         }
     }
 }
@@ -86,6 +100,10 @@ impl App {
 impl Drop for App {
     fn drop(&mut self) {
         // restore terminal
+        if self.releases {
+            execute!(self.terminal.backend_mut(), PopKeyboardEnhancementFlags).unwrap();
+        }
+        execute!(self.terminal.backend_mut(), DisableFocusChange).unwrap();
         disable_raw_mode().unwrap();
         // execute!(self.terminal.backend_mut(), LeaveAlternateScreen).unwrap();
         self.terminal.show_cursor().unwrap();
@@ -0,0 +1,234 @@
+use std::{env, path::PathBuf};
+use tetris::{Rules, Settings};
+use crate::{handling::Handling, keys::{KeyMap, Preset}, records::Records};
+
+/// Command line options of the terminal frontend.
+#[derive(Debug)]
+pub struct Args {
+    /// Fixed piece seed. A fresh random seed is used for every game if unset.
+    pub seed: Option<u64>,
+    pub settings: Settings,
+    /// Glyph of the ghost piece. No ghost is drawn if unset.
+    pub ghost: Option<String>,
+    pub keys: KeyMap,
+    pub handling: Handling,
+    /// File of personal bests. None are kept if unset.
+    pub records: Option<PathBuf>
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Args {
+            seed: None,
+            settings: Settings::default(),
+            ghost: Some(String::from("░")),
+            keys: Preset::default().key_map(),
+            handling: Handling::default(),
+            records: Records::default_path()
+        }
+    }
+}
+
+impl Args {
+    pub fn parse() -> Result<Args, String> {
+        let mut args = Args::default();
+        let argv = env::args().skip(1).collect::<Vec<String>>();
+
+        // rules first, whatever the order, so the other options adjust them
+        if let Some(i) = argv.iter().position(|arg| arg == "--rules") {
+            let val = argv.get(i + 1).ok_or("--rules requires a value")?;
+            args.settings = val.parse::<Rules>()?.settings();
+        }
+
+        let mut key_config = None;
+        let mut iter = argv.into_iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--rules" => {
+                    iter.next();
+                },
+                "--mode" => {
+                    let val = iter.next().ok_or("--mode requires a value")?;
+                    args.settings.mode = val.parse()?;
+                },
+                "--marathon-level" => {
+                    let val = iter.next().ok_or("--marathon-level requires a value")?;
+                    args.settings.marathon_level = val.parse::<u32>().ok()
+                        .filter(|level| *level >= 1)
+                        .ok_or_else(|| format!("invalid marathon level: {}", val))?;
+                },
+                "--endless" => {
+                    args.settings.endless = true;
+                },
+                "--sprint-lines" => {
+                    let val = iter.next().ok_or("--sprint-lines requires a value")?;
+                    args.settings.sprint_lines = val.parse::<u32>().ok()
+                        .filter(|lines| *lines >= 1)
+                        .ok_or_else(|| format!("invalid sprint lines: {}", val))?;
+                },
+                "--ultra-time" => {
+                    let val = iter.next().ok_or("--ultra-time requires a value")?;
+                    let seconds = val.parse::<u64>().ok()
+                        .filter(|seconds| *seconds >= 1)
+                        .ok_or_else(|| format!("invalid ultra time: {}", val))?;
+                    args.settings.ultra_frames = (seconds as f64 * tetris::game::FPS) as u64;
+                },
+                "--cheese-lines" => {
+                    let val = iter.next().ok_or("--cheese-lines requires a value")?;
+                    args.settings.cheese_lines = val.parse::<u32>().ok()
+                        .filter(|lines| *lines >= 1)
+                        .ok_or_else(|| format!("invalid cheese lines: {}", val))?;
+                },
+                "--cheese-rows" => {
+                    let val = iter.next().ok_or("--cheese-rows requires a value")?;
+                    args.settings.cheese_rows = val.parse::<u16>().ok()
+                        .filter(|rows| *rows >= 1)
+                        .ok_or_else(|| format!("invalid cheese rows: {}", val))?;
+                },
+                "--messiness" => {
+                    let val = iter.next().ok_or("--messiness requires a value")?;
+                    args.settings.messiness = val.parse::<u32>().ok()
+                        .filter(|messiness| *messiness <= 100)
+                        .ok_or_else(|| format!("invalid messiness: {}", val))?;
+                },
+                "--zen-clear" => {
+                    let val = iter.next().ok_or("--zen-clear requires a value")?;
+                    args.settings.zen_clear = val.parse()?;
+                },
+                "--zen-clear-rows" => {
+                    let val = iter.next().ok_or("--zen-clear-rows requires a value")?;
+                    args.settings.zen_clear_rows = val.parse::<u16>().ok()
+                        .filter(|rows| *rows >= 1)
+                        .ok_or_else(|| format!("invalid zen clear rows: {}", val))?;
+                },
+                "--records" => {
+                    let val = iter.next().ok_or("--records requires a value")?;
+                    args.records = Some(PathBuf::from(val));
+                },
+                "--no-records" => {
+                    args.records = None;
+                },
+                "--seed" => {
+                    let val = iter.next().ok_or("--seed requires a value")?;
+                    let seed = val.parse::<u64>()
+                        .map_err(|_| format!("invalid seed: {}", val))?;
+                    args.seed = Some(seed);
+                },
+                "--size" => {
+                    let val = iter.next().ok_or("--size requires a value")?;
+                    let (width, height) = val.split_once('x')
+                        .and_then(|(width, height)| Some((width.parse::<u16>().ok()?, height.parse::<u16>().ok()?)))
+                        .filter(|(width, height)| *width >= Settings::MIN_WIDTH && *height >= Settings::MIN_HEIGHT)
+                        .ok_or_else(|| format!("size must be at least {}x{}: {}", Settings::MIN_WIDTH, Settings::MIN_HEIGHT, val))?;
+                    args.settings.width = width;
+                    args.settings.height = height;
+                },
+                "--hidden-rows" => {
+                    let val = iter.next().ok_or("--hidden-rows requires a value")?;
+                    args.settings.hidden_rows = val.parse::<u16>().ok()
+                        .filter(|rows| *rows >= Settings::MIN_HIDDEN_ROWS)
+                        .ok_or_else(|| format!("hidden rows must be at least {}: {}", Settings::MIN_HIDDEN_ROWS, val))?;
+                },
+                "--partial-lock-out" => {
+                    args.settings.partial_lock_out = true;
+                },
+                "--generator" => {
+                    let val = iter.next().ok_or("--generator requires a value")?;
+                    args.settings.generator = val.parse()?;
+                },
+                "--next" => {
+                    let val = iter.next().ok_or("--next requires a value")?;
+                    let count = val.parse::<usize>().ok()
+                        .filter(|count| *count <= Settings::MAX_NEXT)
+                        .ok_or_else(|| format!("next must be 0 to {}: {}", Settings::MAX_NEXT, val))?;
+                    args.settings.next_count = count;
+                },
+                "--lock-delay" => {
+                    let val = iter.next().ok_or("--lock-delay requires a value")?;
+                    args.settings.lock_delay = val.parse::<u32>()
+                        .map_err(|_| format!("invalid lock delay: {}", val))?;
+                },
+                "--are" => {
+                    let val = iter.next().ok_or("--are requires a value")?;
+                    args.settings.are = val.parse::<u32>()
+                        .map_err(|_| format!("invalid entry delay: {}", val))?;
+                },
+                "--line-clear-delay" => {
+                    let val = iter.next().ok_or("--line-clear-delay requires a value")?;
+                    args.settings.line_clear_delay = val.parse::<u32>()
+                        .map_err(|_| format!("invalid line clear delay: {}", val))?;
+                },
+                "--lock-reset" => {
+                    let val = iter.next().ok_or("--lock-reset requires a value")?;
+                    args.settings.lock_reset = val.parse()?;
+                },
+                "--level" => {
+                    let val = iter.next().ok_or("--level requires a value")?;
+                    args.settings.start_level = val.parse::<u32>().ok()
+                        .filter(|level| *level >= 1)
+                        .ok_or_else(|| format!("invalid level: {}", val))?;
+                },
+                "--lines-per-level" => {
+                    let val = iter.next().ok_or("--lines-per-level requires a value")?;
+                    args.settings.lines_per_level = val.parse::<u32>().ok()
+                        .filter(|lines| *lines >= 1)
+                        .ok_or_else(|| format!("invalid lines per level: {}", val))?;
+                },
+                "--gravity" => {
+                    let val = iter.next().ok_or("--gravity requires a value")?;
+                    args.settings.gravity = val.split(',')
+                        .map(|frames| frames.trim().parse::<f64>().ok().filter(|frames| *frames > 0.0))
+                        .collect::<Option<Vec<f64>>>()
+                        .ok_or_else(|| format!("invalid gravity table: {}", val))?;
+                },
+                "--scoring" => {
+                    let val = iter.next().ok_or("--scoring requires a value")?;
+                    args.settings.scoring = val.parse()?;
+                },
+                "--no-hold" => {
+                    args.settings.hold = false;
+                },
+                "--ghost" => {
+                    let val = iter.next().ok_or("--ghost requires a value")?;
+                    if val.chars().count() != 1 {
+                        return Err(format!("ghost must be a single character: {}", val));
+                    }
+                    args.ghost = Some(val);
+                },
+                "--no-ghost" => {
+                    args.ghost = None;
+                },
+                "--das" => {
+                    let val = iter.next().ok_or("--das requires a value")?;
+                    args.handling.das = val.parse::<u32>()
+                        .map_err(|_| format!("invalid das: {}", val))?;
+                },
+                "--arr" => {
+                    let val = iter.next().ok_or("--arr requires a value")?;
+                    args.handling.arr = val.parse::<u32>()
+                        .map_err(|_| format!("invalid arr: {}", val))?;
+                },
+                "--sdf" => {
+                    let val = iter.next().ok_or("--sdf requires a value")?;
+                    args.handling.sdf = val.parse::<u32>().ok()
+                        .filter(|sdf| *sdf >= 1)
+                        .ok_or_else(|| format!("invalid soft drop factor: {}", val))?;
+                },
+                "--keys" => {
+                    let val = iter.next().ok_or("--keys requires a value")?;
+                    args.keys = val.parse::<Preset>()?.key_map();
+                },
+                "--key-config" => {
+                    key_config = Some(iter.next().ok_or("--key-config requires a value")?);
+                },
+                _ => return Err(format!("unknown argument: {}", arg))
+            }
+        }
+
+        // the config file adjusts the preset, whatever the order
+        if let Some(path) = key_config {
+            args.keys.load(&path)?;
+        }
+        Ok(args)
+    }
+}
@@ -1,15 +1,35 @@
 use std::fmt::{Display, Formatter};
-use tui::{
-    style::{Color, Style},
-    text::{Span, Spans},
-};
 use crate::{
-    units::{Pos, Size}
+    units::Size
 };
 
+/// Colour of a block, independent of any frontend palette.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Cyan,
+    Blue,
+    Orange,
+    Yellow,
+    Green,
+    Purple,
+    Red
+}
+
+/// What fills a cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Block {
+    /// Part of a tetro, falling or locked.
+    Tetro(Color),
+    /// Part of a garbage row.
+    Garbage
+}
+
+/// A single cell of a buffer. `None` is an empty cell.
+pub type Cell = Option<Block>;
+
 #[derive(Clone, Debug)]
 pub struct Buffer {
-    cells: Vec<Vec<Option<Color>>>
+    cells: Vec<Vec<Cell>>
 }
 
 impl Buffer {
@@ -23,18 +43,22 @@ impl Buffer {
         }
     }
 
-    pub fn set(&mut self, x: u16, y: u16, val: Option<Color>) {
+    pub fn set(&mut self, x: u16, y: u16, val: Cell) {
         assert!((y as usize) < self.cells.len());
         assert!((x as usize) < self.cells[0].len());
         self.cells[y as usize][x as usize] = val;
     }
 
-    pub fn get(&self, x: u16, y: u16) -> Option<Color> {
+    pub fn get(&self, x: u16, y: u16) -> Cell {
         assert!((y as usize) < self.cells.len());
         assert!((x as usize) < self.cells[0].len());
         self.cells[y as usize][x as usize]
     }
 
+    pub fn rows(&self) -> &[Vec<Cell>] {
+        &self.cells
+    }
+
     pub fn line_completed(&self, y: u16) -> bool {
         for val in &self.cells[y as usize] {
             if val.is_none() {
@@ -44,12 +68,24 @@ impl Buffer {
         true
     }
 
+    pub fn clear_line(&mut self, y: u16) {
+        self.cells[y as usize].fill(None);
+    }
+
+    /// Pushes `line` in at the bottom, shifting every row up and dropping
+    /// the top one.
+    pub fn remove_and_append_line(&mut self, line: Vec<Cell>) {
+        assert_eq!(line.len(), self.width() as usize);
+        self.cells.remove(0);
+        self.cells.push(line);
+    }
+
     pub fn remove_and_prepend_line(&mut self, y: u16) {
         self.cells.remove(y as usize);
         self.cells.insert(0, vec![None; self.width() as usize]);
     }
 
-    pub fn rotate_right(&mut self) -> Buffer {
+    pub fn rotate_right(&self) -> Buffer {
         let size = self.size();
         let new_size = Size::new(size.height, size.width);
         let mut new_buf = Buffer::new(new_size);
@@ -64,27 +100,13 @@ impl Buffer {
         new_buf
     }
 
-    pub fn to_spans(&self) -> Vec<Spans> {
-        self.cells.iter().map(|row| {
-            Spans::from(
-                row.iter().map(|cell| {
-                    if let Some(color) = cell {
-                        Span::styled("█", Style::default().fg(color.clone()))
-                    } else {
-                        Span::styled(" ", Style::default())
-                    }
-                }).collect::<Vec<Span>>()
-            )
-        }).collect::<Vec<Spans>>()
-    }
-
     pub fn new(size: Size) -> Buffer {
         Buffer {
             cells: vec![vec![None; size.width as usize]; size.height as usize]
         }
     }
 
-    pub fn from_vecs(cells: Vec<Vec<Option<Color>>>) -> Buffer {
+    pub fn from_vecs(cells: Vec<Vec<Cell>>) -> Buffer {
         Buffer {
             cells
         }
@@ -98,7 +120,7 @@ impl Display for Buffer {
         let height = self.height();
         for y in 0..height {
             for x in 0..width {
-                if let Some(_) = self.get(x, y) {
+                if self.get(x, y).is_some() {
                     count += 1;
                 }
             }
@@ -0,0 +1,200 @@
+use std::time::Duration;
+use crate::{
+    buffer::Buffer,
+    scoring::Clear,
+    playground::{Phase, Playground},
+    settings::{Mode, Settings},
+    tetro::Tetro,
+    units::{Pos, Size}
+};
+
+const FRAMES_PER_SECOND: u64 = 60;
+
+/// Frames per second. All engine timings are counted in frames.
+pub const FPS: f64 = FRAMES_PER_SECOND as f64;
+/// Game time advanced by one [`Game::step`], one frame.
+pub const TICK: Duration = Duration::from_nanos(1_000_000_000 / FRAMES_PER_SECOND);
+
+/// Player actions understood by the engine. Frontends translate their own
+/// key events into these.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Input {
+    MoveLeft,
+    MoveRight,
+    RotateRight,
+    RotateLeft,
+    Rotate180,
+    SoftDrop,
+    HardDrop,
+    Hold
+}
+
+/// Why a game ended by topping out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TopOut {
+    /// A new tetro overlapped the stack where it spawned.
+    BlockOut,
+    /// A tetro locked entirely above the visible field.
+    LockOut,
+    /// A tetro locked partly above the visible field, with
+    /// [`Settings::partial_lock_out`] set.
+    PartialLockOut
+}
+
+/// UI-agnostic game engine.
+///
+/// One call to [`Game::step`] advances the game by one frame. Once the game
+/// tops out or reaches the goal of its mode it is over and further steps do nothing until
+/// [`Game::reset`] is called.
+///
+/// The piece sequence is determined by the settings and the seed, so two
+/// games with the same settings, seed and inputs on the same steps play out
+/// identically.
+#[derive(Debug)]
+pub struct Game {
+    playground: Playground,
+    frame: u64,
+    over: bool
+}
+
+impl Game {
+    /// Advances the game by one [`TICK`]. Returns `false` once the game is over.
+    pub fn step(&mut self) -> bool {
+        if !self.over {
+            self.frame += 1;
+            if !self.playground.on_tick() {
+                self.over = true;
+            } else if self.settings().mode == Mode::Ultra && self.frame >= self.settings().ultra_frames {
+                self.playground.completed = true;
+                self.over = true;
+            }
+        }
+        !self.over
+    }
+
+    pub fn input(&mut self, input: Input) {
+        if !self.over {
+            self.playground.on_input(input);
+        }
+    }
+
+    /// Starts over with the same seed.
+    pub fn reset(&mut self) {
+        self.restart(self.seed());
+    }
+
+    /// Starts over with a new seed.
+    pub fn restart(&mut self, seed: u64) {
+        self.playground.clear(seed);
+        self.frame = 0;
+        self.over = false;
+    }
+
+    pub fn is_over(&self) -> bool { self.over }
+    /// Whether the game ended by reaching the goal of its mode.
+    pub fn is_complete(&self) -> bool { self.playground.completed }
+    /// Frames played, counting the one that ended the game.
+    pub fn frame(&self) -> u64 { self.frame }
+    /// Game time played, by the engine clock.
+    pub fn time(&self) -> Duration { to_time(self.frame) }
+
+    /// Game time left before a [`Mode::Ultra`] game ends.
+    pub fn time_left(&self) -> Option<Duration> {
+        match self.settings().mode {
+            Mode::Ultra => Some(to_time(self.settings().ultra_frames.saturating_sub(self.frame))),
+            _ => None
+        }
+    }
+    pub fn top_out(&self) -> Option<TopOut> { self.playground.top_out }
+    pub fn phase(&self) -> Phase { self.playground.phase }
+    pub fn score(&self) -> u32 { self.playground.score }
+    pub fn level(&self) -> u32 { self.playground.level }
+    pub fn lines(&self) -> u32 { self.playground.lines }
+    pub fn pieces(&self) -> u32 { self.playground.pieces }
+    pub fn top_outs(&self) -> u32 { self.playground.top_outs }
+    pub fn garbage_lines(&self) -> u32 { self.playground.garbage_lines }
+
+    /// The last lock that cleared lines or was a T-spin.
+    pub fn last_clear(&self) -> Option<&Clear> { self.playground.last_clear.as_ref() }
+    pub fn seed(&self) -> u64 { self.playground.seed }
+    pub fn settings(&self) -> &Settings { &self.playground.settings }
+    /// Size of the visible field.
+    pub fn size(&self) -> Size { self.playground.size() }
+    pub fn hidden_rows(&self) -> u16 { self.playground.hidden_rows() }
+
+    /// Locked cells of the board, without the active tetro. The first
+    /// [`Game::hidden_rows`] rows lie above the visible field.
+    pub fn board(&self) -> &Buffer { &self.playground.buffer }
+
+    /// The falling tetro and its position on the visible field.
+    pub fn tetro(&self) -> Option<(&Tetro, Pos)> {
+        self.playground.tetro.as_ref().map(|tetro| (tetro, self.playground.tetro_pos))
+    }
+
+    /// The falling tetro at its hard drop destination.
+    pub fn ghost(&self) -> Option<(&Tetro, Pos)> {
+        self.playground.tetro.as_ref().zip(self.playground.ghost_pos())
+    }
+
+    /// Number of upcoming tetros shown.
+    pub fn next_count(&self) -> usize { self.playground.next_count() }
+
+    /// The upcoming tetros, in order. Empty until the first step.
+    pub fn next(&self) -> impl Iterator<Item = &Tetro> {
+        self.playground.next.iter().take(self.playground.next_count())
+    }
+
+    /// The held tetro, and whether hold was already used for this tetro.
+    pub fn hold(&self) -> Option<(&Tetro, bool)> {
+        self.playground.hold.as_ref().map(|tetro| (tetro, self.playground.hold_used))
+    }
+
+    pub fn new(settings: Settings, seed: u64) -> Game {
+        Game {
+            playground: Playground::new(settings, seed),
+            frame: 0,
+            over: false
+        }
+    }
+}
+
+fn to_time(frames: u64) -> Duration {
+    Duration::from_secs_f64(frames as f64 / FPS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tetro::Kind;
+
+    /// Plays a fixed input script and returns the tetros dealt, the score
+    /// and the final board.
+    fn play(seed: u64) -> (Vec<Kind>, u32, String) {
+        let script = [Input::MoveLeft, Input::RotateRight, Input::HardDrop, Input::MoveRight,
+                      Input::MoveRight, Input::HardDrop, Input::Hold, Input::SoftDrop, Input::HardDrop];
+        let mut game = Game::new(Settings::default(), seed);
+        let mut kinds = Vec::new();
+        for frame in 0..600 {
+            game.step();
+            if let Some((tetro, _)) = game.tetro() {
+                if kinds.is_empty() || frame % 10 == 0 {
+                    kinds.push(tetro.kind);
+                }
+            }
+            if frame % 10 == 0 {
+                game.input(script[frame / 10 % script.len()]);
+            }
+        }
+        (kinds, game.score(), format!("{:?}", game.board().rows()))
+    }
+
+    #[test]
+    fn same_seed_and_inputs_play_the_same() {
+        assert_eq!(play(42), play(42));
+    }
+
+    #[test]
+    fn seed_changes_the_pieces() {
+        assert_ne!(play(1).0, play(2).0);
+    }
+}
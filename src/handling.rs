@@ -0,0 +1,251 @@
+use std::{iter, time::{Duration, Instant}};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use tetris::Input;
+use crate::keys::Action;
+
+// Terminals without keyboard enhancement report no key releases, so there a
+// held key is recognised by the terminal's key repeat: after the event that
+// follows the press, events of the same key closer together than REPEAT_GAP
+// are repeats, not taps, and a held key counts as released once its repeats
+// stop for twice their interval, within MIN_RELEASE_GAP and REPEAT_GAP.
+const REPEAT_GAP: Duration = Duration::from_millis(100);
+const MIN_RELEASE_GAP: Duration = Duration::from_millis(50);
+// longest terminal repeat delay, before the first repeat of a held key
+const HOLD_GAP: Duration = Duration::from_millis(700);
+
+/// How held keys repeat, in frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Handling {
+    /// Delayed auto shift: frames a move key is held before it repeats.
+    pub das: u32,
+    /// Auto repeat rate: frames between repeated moves. 0 moves to the
+    /// wall at once.
+    pub arr: u32,
+    /// Soft drop factor: how many times faster than gravity a held soft
+    /// drop falls.
+    pub sdf: u32
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Handling {
+            das: 10,
+            arr: 2,
+            sdf: 20
+        }
+    }
+}
+
+/// A key held down.
+#[derive(Debug)]
+struct Held {
+    action: Action,
+    code: KeyCode,
+    /// Frame of the first press.
+    pressed_frame: u64,
+    pressed_at: Instant,
+    /// Last event of the key, press or repeat.
+    seen_at: Instant,
+    /// Time between the last two terminal repeats.
+    repeat_gap: Duration,
+    /// Frame the key repeats next, once it is known to be held.
+    repeat_frame: Option<u64>
+}
+
+impl Held {
+    fn new(action: Action, code: KeyCode, now: Instant, frame: u64) -> Held {
+        Held {
+            action,
+            code,
+            pressed_frame: frame,
+            pressed_at: now,
+            seen_at: now,
+            repeat_gap: REPEAT_GAP,
+            repeat_frame: None
+        }
+    }
+
+    fn is_down(&self, now: Instant) -> bool {
+        let gap = match self.repeat_frame {
+            Some(_) => (self.repeat_gap * 2).clamp(MIN_RELEASE_GAP, REPEAT_GAP),
+            None => HOLD_GAP
+        };
+        now < self.seen_at + gap
+    }
+}
+
+/// Turns held move and soft drop keys into repeated inputs, frame by frame.
+#[derive(Debug)]
+pub struct Controller {
+    pub handling: Handling,
+    /// Whether the terminal reports key releases. Otherwise held keys are
+    /// told apart from taps by their repeats.
+    pub releases: bool,
+    /// The move key held last; it wins over the other direction.
+    shift: Option<Held>,
+    soft_drop: Option<Held>,
+    /// Rows of held soft drop accumulated but not dropped yet.
+    drop: f64
+}
+
+impl Controller {
+    /// Records a key press or repeat on game frame `frame`. Returns `false`
+    /// if it is a repeat of a held key, which [`Controller::update`] takes
+    /// care of.
+    pub fn on_key(&mut self, action: Action, key: &KeyEvent, now: Instant, frame: u64) -> bool {
+        if self.releases && key.kind == KeyEventKind::Repeat {
+            return false
+        }
+        let code = key.code;
+        let (slot, delay) = match action {
+            Action::MoveLeft | Action::MoveRight => (&mut self.shift, self.handling.das),
+            Action::SoftDrop => (&mut self.soft_drop, 0),
+            _ => return true
+        };
+
+        if self.releases {
+            let mut held = Held::new(action, code, now, frame);
+            held.repeat_frame = Some(frame + delay as u64);
+            *slot = Some(held);
+            return true
+        }
+
+        match slot {
+            // the terminal repeat delay is longer than a double tap, so the
+            // event after the press is always a tap
+            Some(held) if held.code == code && held.is_down(now) && held.seen_at == held.pressed_at => {
+                held.seen_at = now;
+                true
+            },
+            Some(held) if held.code == code && held.is_down(now) => {
+                let gap = now.duration_since(held.seen_at);
+                let repeat = gap < REPEAT_GAP;
+                held.seen_at = now;
+                if repeat {
+                    held.repeat_gap = gap;
+                    if held.repeat_frame.is_none() {
+                        held.repeat_frame = Some(frame.max(held.pressed_frame + delay as u64));
+                    }
+                }
+                !repeat && held.repeat_frame.is_none()
+            },
+            _ => {
+                *slot = Some(Held::new(action, code, now, frame));
+                true
+            }
+        }
+    }
+
+    /// Records a key release, reported only by terminals with keyboard
+    /// enhancement.
+    pub fn on_release(&mut self, code: KeyCode) {
+        if self.shift.as_ref().is_some_and(|held| held.code == code) {
+            self.shift = None;
+        }
+        if self.soft_drop.as_ref().is_some_and(|held| held.code == code) {
+            self.soft_drop = None;
+        }
+    }
+
+    /// Inputs due from held keys on game frame `frame`. `frames_per_row` is
+    /// the current gravity, and `limit` caps the inputs of one key, such as
+    /// an instant shift to the wall.
+    pub fn update(&mut self, now: Instant, frame: u64, frames_per_row: f64, limit: usize) -> Vec<Input> {
+        let mut inputs = Vec::new();
+        if !self.releases {
+            if self.shift.as_ref().is_some_and(|held| !held.is_down(now)) {
+                self.shift = None;
+            }
+            if self.soft_drop.as_ref().is_some_and(|held| !held.is_down(now)) {
+                self.soft_drop = None;
+            }
+        }
+
+        // auto shift
+        if let Some(held) = &mut self.shift {
+            if let (Some(input), Some(repeat_frame)) = (held.action.input(), held.repeat_frame) {
+                if frame >= repeat_frame {
+                    let arr = self.handling.arr as u64;
+                    let count = if arr == 0 { limit } else { 1 };
+                    inputs.extend(iter::repeat_n(input, count));
+                    held.repeat_frame = Some(frame + arr.max(1));
+                }
+            }
+        }
+
+        // soft drop
+        match &self.soft_drop {
+            Some(held) if held.repeat_frame.is_some() => {
+                self.drop += self.handling.sdf.max(1) as f64 / frames_per_row;
+                let rows = self.drop.floor();
+                self.drop -= rows;
+                inputs.extend(iter::repeat_n(Input::SoftDrop, (rows as usize).min(limit)));
+            },
+            _ => self.drop = 0.0
+        }
+        inputs
+    }
+
+    /// Forgets all held keys.
+    pub fn release(&mut self) {
+        self.shift = None;
+        self.soft_drop = None;
+        self.drop = 0.0;
+    }
+
+    pub fn new(handling: Handling) -> Controller {
+        Controller {
+            handling,
+            releases: false,
+            shift: None,
+            soft_drop: None,
+            drop: 0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn key(kind: KeyEventKind) -> KeyEvent {
+        KeyEvent::new_with_kind(KeyCode::Left, KeyModifiers::NONE, kind)
+    }
+
+    #[test]
+    fn fast_double_tap_moves_twice() {
+        let mut controller = Controller::new(Handling::default());
+        let now = Instant::now();
+        assert!(controller.on_key(Action::MoveLeft, &key(KeyEventKind::Press), now, 0));
+        assert!(controller.on_key(Action::MoveLeft, &key(KeyEventKind::Press), now + Duration::from_millis(50), 3));
+    }
+
+    #[test]
+    fn terminal_repeats_start_auto_shift() {
+        let mut controller = Controller::new(Handling::default());
+        let now = Instant::now();
+        let at = |ms| now + Duration::from_millis(ms);
+        assert!(controller.on_key(Action::MoveLeft, &key(KeyEventKind::Press), at(0), 0));
+        // the first repeat after the terminal's repeat delay still moves
+        assert!(controller.on_key(Action::MoveLeft, &key(KeyEventKind::Press), at(400), 24));
+        assert!(!controller.on_key(Action::MoveLeft, &key(KeyEventKind::Press), at(430), 26));
+        assert_eq!(controller.update(at(431), 26, 60.0, 10), vec![Input::MoveLeft]);
+        // repeats stopped, the key was released
+        assert!(controller.update(at(600), 36, 60.0, 10).is_empty());
+    }
+
+    #[test]
+    fn released_key_stops_auto_shift() {
+        let mut controller = Controller::new(Handling::default());
+        controller.releases = true;
+        let now = Instant::now();
+        assert!(controller.on_key(Action::MoveLeft, &key(KeyEventKind::Press), now, 0));
+        assert!(controller.update(now, 9, 60.0, 10).is_empty());
+        assert_eq!(controller.update(now, 10, 60.0, 10), vec![Input::MoveLeft]);
+        assert!(!controller.on_key(Action::MoveLeft, &key(KeyEventKind::Repeat), now, 11));
+        assert_eq!(controller.update(now, 12, 60.0, 10), vec![Input::MoveLeft]);
+        controller.on_release(KeyCode::Left);
+        assert!(controller.update(now, 14, 60.0, 10).is_empty());
+    }
+}
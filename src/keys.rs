@@ -0,0 +1,246 @@
+use std::{collections::HashMap, fmt::{self, Display, Formatter}, fs, str::FromStr};
+use crossterm::event::{KeyCode, KeyEvent};
+use tetris::Input;
+
+/// Everything a key can be bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateRight,
+    RotateLeft,
+    Rotate180,
+    Hold,
+    Pause,
+    Restart,
+    Quit
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::MoveLeft, Action::MoveRight, Action::SoftDrop, Action::HardDrop,
+        Action::RotateRight, Action::RotateLeft, Action::Rotate180, Action::Hold,
+        Action::Pause, Action::Restart, Action::Quit
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::SoftDrop => "soft_drop",
+            Action::HardDrop => "hard_drop",
+            Action::RotateRight => "rotate_cw",
+            Action::RotateLeft => "rotate_ccw",
+            Action::Rotate180 => "rotate_180",
+            Action::Hold => "hold",
+            Action::Pause => "pause",
+            Action::Restart => "restart",
+            Action::Quit => "quit"
+        }
+    }
+
+    /// The engine input of a gameplay action.
+    pub fn input(&self) -> Option<Input> {
+        match self {
+            Action::MoveLeft => Some(Input::MoveLeft),
+            Action::MoveRight => Some(Input::MoveRight),
+            Action::SoftDrop => Some(Input::SoftDrop),
+            Action::HardDrop => Some(Input::HardDrop),
+            Action::RotateRight => Some(Input::RotateRight),
+            Action::RotateLeft => Some(Input::RotateLeft),
+            Action::Rotate180 => Some(Input::Rotate180),
+            Action::Hold => Some(Input::Hold),
+            Action::Pause | Action::Restart | Action::Quit => None
+        }
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::ALL.iter()
+            .find(|action| action.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown action: {}", s))
+    }
+}
+
+/// Built-in key bindings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Preset {
+    /// Arrows to move, Z/X/A to rotate, space to drop.
+    #[default]
+    Arrows,
+    /// WASD to move and drop, J/K/L to rotate.
+    Wasd,
+    /// HJKL to move and drop, F/D/S to rotate.
+    Vim
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Arrows, Preset::Wasd, Preset::Vim];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::Arrows => "arrows",
+            Preset::Wasd => "wasd",
+            Preset::Vim => "vim"
+        }
+    }
+
+    pub fn key_map(&self) -> KeyMap {
+        use KeyCode::{Char, Down, Esc, Left, Right, Up};
+        let bindings: &[(Action, &[KeyCode])] = match self {
+            Preset::Arrows => &[
+                (Action::MoveLeft, &[Left]),
+                (Action::MoveRight, &[Right]),
+                (Action::SoftDrop, &[Down]),
+                (Action::HardDrop, &[Char(' ')]),
+                (Action::RotateRight, &[Up, Char('x')]),
+                (Action::RotateLeft, &[Char('z')]),
+                (Action::Rotate180, &[Char('a')]),
+                (Action::Hold, &[Char('c')])
+            ],
+            Preset::Wasd => &[
+                (Action::MoveLeft, &[Char('a')]),
+                (Action::MoveRight, &[Char('d')]),
+                (Action::SoftDrop, &[Char('s')]),
+                (Action::HardDrop, &[Char('w')]),
+                (Action::RotateRight, &[Char('k')]),
+                (Action::RotateLeft, &[Char('j')]),
+                (Action::Rotate180, &[Char('l')]),
+                (Action::Hold, &[Char(' ')])
+            ],
+            Preset::Vim => &[
+                (Action::MoveLeft, &[Char('h')]),
+                (Action::MoveRight, &[Char('l')]),
+                (Action::SoftDrop, &[Char('j')]),
+                (Action::HardDrop, &[Char('k')]),
+                (Action::RotateRight, &[Char('f')]),
+                (Action::RotateLeft, &[Char('d')]),
+                (Action::Rotate180, &[Char('s')]),
+                (Action::Hold, &[Char('a')])
+            ]
+        };
+
+        // shared by all presets
+        let common: &[(Action, &[KeyCode])] = &[
+            (Action::Pause, &[Char('p'), Esc]),
+            (Action::Restart, &[Char('r')]),
+            (Action::Quit, &[Char('q')])
+        ];
+
+        let mut key_map = KeyMap::default();
+        for (action, keys) in bindings.iter().chain(common) {
+            key_map.bind(*action, keys);
+        }
+        key_map
+    }
+}
+
+impl Display for Preset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Preset::ALL.iter()
+            .find(|preset| preset.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown preset: {}", s))
+    }
+}
+
+/// Which action each key triggers. A key has at most one action, an action
+/// any number of keys.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyMap {
+    actions: HashMap<KeyCode, Action>
+}
+
+impl KeyMap {
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.actions.get(&key.code).copied()
+    }
+
+    /// Binds `keys` to `action`, replacing the keys it had before.
+    pub fn bind(&mut self, action: Action, keys: &[KeyCode]) {
+        self.actions.retain(|_, bound| *bound != action);
+        for key in keys {
+            self.actions.insert(*key, action);
+        }
+    }
+
+    /// Applies the bindings of a config file. Each line binds an action to
+    /// a comma separated list of keys, e.g. `hold = c, tab`. Blank lines
+    /// and lines starting with `#` are skipped.
+    pub fn load(&mut self, path: &str) -> Result<(), String> {
+        let config = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read {}: {}", path, err))?;
+        for (i, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (action, keys) = line.split_once('=')
+                .ok_or_else(|| format!("{}:{}: expected <action> = <keys>", path, i + 1))?;
+            let action = action.trim().parse::<Action>()
+                .map_err(|err| format!("{}:{}: {}", path, i + 1, err))?;
+            let keys = keys.split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(parse_key)
+                .collect::<Result<Vec<KeyCode>, String>>()
+                .map_err(|err| format!("{}:{}: {}", path, i + 1, err))?;
+            self.bind(action, &keys);
+        }
+        Ok(())
+    }
+}
+
+/// Parses a key name: a single character, `space`, `comma`, an arrow such as `left`,
+/// `f1` to `f12`, or one of `enter`, `esc`, `tab`, `backspace`, `insert`,
+/// `delete`, `home`, `end`, `pageup` and `pagedown`.
+pub fn parse_key(name: &str) -> Result<KeyCode, String> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(c));
+    }
+
+    let key = match name.to_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "comma" => KeyCode::Char(','),
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "insert" => KeyCode::Insert,
+        "delete" => KeyCode::Delete,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        other => match other.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+            Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+            _ => return Err(format!("unknown key: {}", name))
+        }
+    };
+    Ok(key)
+}
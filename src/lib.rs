@@ -0,0 +1,23 @@
+//! Headless tetris engine.
+//!
+//! The engine knows nothing about terminals or key codes. Frontends drive a
+//! [`Game`] with [`Game::step`] and [`Game::input`], and read the board,
+//! the active tetro, the queue of upcoming tetros ([`Game::next`], of which
+//! [`Game::next_count`] are shown) and the score back for rendering.
+
+pub mod buffer;
+pub mod game;
+mod playground;
+pub mod randomizer;
+pub mod scoring;
+pub mod settings;
+pub mod srs;
+pub mod tetro;
+pub mod units;
+
+pub use buffer::{Block, Buffer, Cell, Color};
+pub use game::{Game, Input, TopOut};
+pub use playground::Phase;
+pub use randomizer::{Generator, Randomizer};
+pub use scoring::{Clear, Scoring, Spin};
+pub use settings::{LockReset, Mode, Rules, Settings, ZenClear};
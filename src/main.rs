@@ -1,19 +1,36 @@
-use std::io;
+use std::{io, process};
 
 mod app;
-mod buffer;
+mod args;
+mod handling;
+mod keys;
+mod records;
 mod screen;
-mod playground;
-mod tetro;
-mod units;
 
 use app::App;
-use std::panic;
+use args::Args;
 
 fn main() -> Result<(), io::Error> {
-    // panic::set_hook(Box::new(|_info| { }));
+    let args = Args::parse().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        eprintln!("usage: tetris [--mode marathon|sprint|ultra|cheese|zen] [--marathon-level <n>] [--endless]");
+        eprintln!("              [--sprint-lines <n>] [--ultra-time <s>]");
+        eprintln!("              [--cheese-lines <n>] [--cheese-rows <n>] [--messiness <0-100>]");
+        eprintln!("              [--zen-clear top|board] [--zen-clear-rows <n>]");
+        eprintln!("              [--records <path> | --no-records]");
+        eprintln!("              [--rules modern|classic] [--seed <u64>] [--size <width>x<height>]");
+        eprintln!("              [--hidden-rows <n>] [--partial-lock-out]");
+        eprintln!("              [--generator random|bag|history|nes]");
+        eprintln!("              [--ghost <char> | --no-ghost] [--no-hold] [--next <0-6>]");
+        eprintln!("              [--lock-delay <frames>] [--lock-reset move|step|infinity]");
+        eprintln!("              [--are <frames>] [--line-clear-delay <frames>]");
+        eprintln!("              [--level <n>] [--lines-per-level <n>] [--gravity <frames,...>]");
+        eprintln!("              [--scoring guideline|nes|classic]");
+        eprintln!("              [--keys arrows|wasd|vim] [--key-config <path>]");
+        eprintln!("              [--das <frames>] [--arr <frames>] [--sdf <n>]");
+        process::exit(2);
+    });
 
-    let mut app = App::new().unwrap();
-    app.run().unwrap();
-    Ok(())
+    let mut app = App::new(&args)?;
+    app.run()
 }
@@ -1,260 +1,1052 @@
-use tui::{
-    backend::Backend,
-    terminal::{Terminal, Frame},
-    backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Style, Modifier},
-    text::{Span, Spans},
-    widgets::{Borders, BorderType, Block, Paragraph},
-};
-use crossterm::{
-    execute,
-    event::{self, Event, KeyCode, KeyEvent},
-    terminal::{enable_raw_mode, disable_raw_mode, EnterAlternateScreen,
-               LeaveAlternateScreen}
-};
+use std::collections::VecDeque;
+use rand::{Rng, SeedableRng, rngs::StdRng};
 use crate::{
-    tetro::{Tetro, TetroState},
-    buffer::Buffer,
+    game::{Input, TopOut},
+    randomizer::Randomizer,
+    scoring::{Clear, Spin},
+    settings::{LockReset, Mode, Settings, ZenClear},
+    srs,
+    tetro::{Kind, Orientation, Tetro},
+    buffer::{Block, Buffer, Cell},
     units::{Pos, Size}
 };
 
-const SIZE: Size = Size { width: 12, height: 20 };
-const MAX_DESCENT_SPEED: u32 = 10;
+// moves and rotations that reset the lock delay, per row reached
+const MAX_LOCK_RESETS: u32 = 15;
+
+// points per row fallen
+const SOFT_DROP_POINTS: u32 = 1;
+const HARD_DROP_POINTS: u32 = 2;
+
+/// What the playground is doing between two tetros.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// A tetro is falling.
+    Falling,
+    /// Completed lines stay on the board for this many more frames before
+    /// they are removed and scored.
+    LineClear { frames: u32, spin: Spin },
+    /// The next tetro spawns after this many more frames, or on the next
+    /// one if there is no entry delay.
+    Entry(u32)
+}
 
 #[derive(Debug)]
 pub struct Playground {
     pub score: u32,
 
+    pub settings: Settings,
+    pub seed: u64,
+    pub randomizer: Box<dyn Randomizer>,
+
     pub tetro_pos: Pos,
     pub tetro: Option<Tetro>,
-    pub next: Option<Tetro>,
-    pub debug_msg: String,
+    /// Upcoming tetros, at least one even if none are shown.
+    pub next: VecDeque<Tetro>,
+    pub hold: Option<Tetro>,
+    pub hold_used: bool,
 
     pub buffer: Buffer,
+    pub phase: Phase,
+    /// Inputs received while no tetro was falling, applied once the next
+    /// one spawns. Soft drops are not kept, as they come from a held key.
+    pub buffered: Vec<Input>,
 
     // speed
-    pub descent_speed: u32,
-    pub tick_count: u32
+    pub level: u32,
+    pub lines: u32,
+    /// Tetros locked so far.
+    pub pieces: u32,
+    /// Rows of gravity accumulated but not fallen yet.
+    pub gravity: f64,
+
+    // lock delay
+    pub lock_frames: u32,
+    pub lock_resets: u32,
+    pub lowest_y: i32,
+
+    // scoring
+    /// Kick used by the last move if it was a rotation.
+    pub last_kick: Option<usize>,
+    /// Whether the last rotation was a 180, whose kicks have no TST kick.
+    pub last_flip: bool,
+    pub last_clear: Option<Clear>,
+    pub back_to_back: bool,
+    pub combo: Option<u32>,
+
+    // garbage
+    /// Deals the garbage holes, seeded apart from the randomizer.
+    pub garbage_rng: StdRng,
+    /// Column of the hole of the last garbage row.
+    pub garbage_hole: Option<u16>,
+    /// Garbage rows cleared so far.
+    pub garbage_lines: u32,
+
+    pub top_out: Option<TopOut>,
+    /// Top outs survived in [`Mode::Zen`].
+    pub top_outs: u32,
+    /// Whether the goal of the mode was reached.
+    pub completed: bool
 }
 
 impl Playground {
-    pub fn size(&self) -> Size { SIZE }
+    pub fn size(&self) -> Size { self.settings.size() }
+    pub fn hidden_rows(&self) -> u16 { self.settings.hidden_rows() }
 
     pub fn on_tick(&mut self) -> bool {
-        // remove line completed
-        if self.remove_line_completed() {
-            return true;
+        if self.top_out.is_some() || self.completed {
+            return false;
         }
 
-        // new tetro
-        if self.tetro.is_none() {
-            // new next
-            if self.next.is_none() {
-                self.next = Some(Tetro::new());
-            }
-
-            self.tetro = if let Some(new_tetro) = self.next.take() {
-                if self.next.is_none() {
-                    self.next = Some(Tetro::new());
+        match self.phase {
+            Phase::Falling => {},
+            Phase::LineClear { frames, spin } => {
+                if frames > 1 {
+                    self.phase = Phase::LineClear { frames: frames - 1, spin };
+                } else {
+                    self.clear_lines(spin);
                 }
-
-                let pl_size = self.size();
-                let new_size = new_tetro.size();
-                let new_pos = Pos::new(pl_size.mid_x() - new_size.mid_x(), 0);
-
-                // finish game
-                if self.is_reach_bottom(new_pos.x, new_pos.y, &new_tetro) {
-                    self.place(new_pos.x, new_pos.y, &new_tetro);
-                    return false;
+                return true;
+            },
+            Phase::Entry(frames) => {
+                if frames > 1 {
+                    self.phase = Phase::Entry(frames - 1);
+                    return true;
                 }
+                return self.spawn();
+            }
+        }
 
-                self.tetro_pos = new_pos;
-                Some(new_tetro)
-            } else {
-                assert!(false);
-                None
-            };
-
-            // self.debug_msg = format!("{:?}", self.next);
-            return true;
+        // gravity
+        self.gravity += 1.0 / self.settings.frames_per_row(self.level);
+        while self.gravity >= 1.0 {
+            self.gravity -= 1.0;
+            if !self.fall() {
+                self.gravity = 0.0;
+            }
         }
 
-        // descent tetro soft
-        self.tick_count += 1;
-        if self.tick_count > (MAX_DESCENT_SPEED - self.descent_speed) {
-            self.descend_soft();
-            return true
+        // lock delay
+        if self.is_grounded() {
+            self.lock_frames += 1;
+            if self.lock_frames >= self.settings.lock_delay.max(1) {
+                self.lock();
+            }
         }
 
         true
     }
 
-    pub fn on_keydown(&mut self, key: &KeyEvent) {
-        match key.code {
-            KeyCode::Left => {
-                // self.debug_msg = String::from("left");
+    pub fn on_input(&mut self, input: Input) {
+        if self.phase != Phase::Falling {
+            if input != Input::SoftDrop {
+                self.buffered.push(input);
+            }
+            return;
+        }
+
+        match input {
+            Input::MoveLeft => {
                 self.move_left();
             },
-            KeyCode::Right => {
-                // self.debug_msg = String::from("right");
+            Input::MoveRight => {
                 self.move_right();
             },
-            KeyCode::Up => {
-                // self.debug_msg = String::from("up");
+            Input::RotateRight => {
                 self.rotate_right();
             },
-            KeyCode::Down => {
-                // self.debug_msg = String::from("down");
-                self.descend_soft();
+            Input::RotateLeft => {
+                self.rotate_left();
+            },
+            Input::Rotate180 => {
+                self.rotate_180();
             },
-            KeyCode::Char(char) if char == ' ' => {
-                // self.debug_msg = String::from("space");
+            Input::SoftDrop => {
+                if self.descend_soft() {
+                    self.award(SOFT_DROP_POINTS);
+                }
+            },
+            Input::HardDrop => {
                 self.descend_hard();
             },
-            _ => {}
+            Input::Hold => {
+                self.hold();
+            }
         }
     }
 
-    pub fn clear(&mut self) {
-        self.buffer = Buffer::new(SIZE);
+    pub fn clear(&mut self, seed: u64) {
+        self.buffer = Buffer::new(self.settings.board_size());
+        self.phase = Phase::Entry(0);
+        self.buffered.clear();
         self.score = 0;
+        self.seed = seed;
+        self.randomizer = self.settings.generator.randomizer(seed);
         self.tetro_pos = Pos::new(0, 0);
         self.tetro = None;
-        self.next = None;
-        self.debug_msg = String::from("");
-        self.descent_speed = 1;
-        self.tick_count = 0;
+        self.next.clear();
+        self.hold = None;
+        self.hold_used = false;
+        self.level = self.settings.start_level;
+        self.lines = 0;
+        self.pieces = 0;
+        self.gravity = 0.0;
+        self.reset_lock();
+        self.last_kick = None;
+        self.last_flip = false;
+        self.last_clear = None;
+        self.back_to_back = false;
+        self.combo = None;
+        self.garbage_rng = StdRng::seed_from_u64(!seed);
+        self.garbage_hole = None;
+        self.garbage_lines = 0;
+        self.top_out = None;
+        self.top_outs = 0;
+        self.completed = false;
+        self.fill_garbage();
     }
 
-    pub fn new() -> Playground {
-        Playground {
-            buffer: Buffer::new(SIZE),
+    pub fn new(settings: Settings, seed: u64) -> Playground {
+        let mut playground = Playground {
+            buffer: Buffer::new(settings.board_size()),
+            phase: Phase::Entry(0),
+            buffered: Vec::new(),
             score: 0,
+            randomizer: settings.generator.randomizer(seed),
+            level: settings.start_level,
+            settings,
+            seed,
             tetro_pos: Pos::new(0, 0),
             tetro: None,
-            next: None,
-            debug_msg: String::from(""),
+            next: VecDeque::new(),
+            hold: None,
+            hold_used: false,
 
-            descent_speed: 1,
-            tick_count: 0
+            lines: 0,
+            pieces: 0,
+            gravity: 0.0,
+
+            lock_frames: 0,
+            lock_resets: 0,
+            lowest_y: 0,
+
+            last_kick: None,
+            last_flip: false,
+            last_clear: None,
+            back_to_back: false,
+            combo: None,
+
+            garbage_rng: StdRng::seed_from_u64(!seed),
+            garbage_hole: None,
+            garbage_lines: 0,
+
+            top_out: None,
+            top_outs: 0,
+            completed: false
+        };
+        playground.fill_garbage();
+        playground
+    }
+
+    /// Number of upcoming tetros shown.
+    pub fn next_count(&self) -> usize {
+        self.settings.next_count.min(Settings::MAX_NEXT)
+    }
+
+    /// Tops up the next queue from the randomizer.
+    fn fill_next(&mut self) {
+        while self.next.len() < self.next_count().max(1) {
+            self.next.push_back(Tetro::new(self.randomizer.next_kind()));
         }
     }
 
-    pub fn move_left(&mut self) {
-        if self.tetro_pos.x == 0 { return }
+    /// Takes the next tetro and refills the queue.
+    fn take_next(&mut self) -> Tetro {
+        self.fill_next();
+        let next = self.next.pop_front().unwrap();
+        self.fill_next();
+        next
+    }
 
-        if let Some(mut tetro) = self.tetro.take() {
-            self.move_to(self.tetro_pos.x - 1, self.tetro_pos.y, &mut tetro);
-            self.tetro = Some(tetro);
+    /// Guideline spawn position: centered, rounding left, with the tetro
+    /// just above the visible field. It drops one row right away if nothing
+    /// is in the way.
+    fn spawn_pos(&self, tetro: &Tetro) -> Pos {
+        let Size { width, height } = tetro.size();
+        let x = (self.size().width - width) as i32 / 2;
+        let y = -(height as i32);
+        if self.fits(x, y, tetro) && self.fits(x, y + 1, tetro) {
+            Pos::new(x, y + 1)
+        } else {
+            Pos::new(x, y)
         }
     }
 
-    pub fn move_right(&mut self) {
-        if let Some(mut tetro) = self.tetro.take() {
-            self.move_to(self.tetro_pos.x + 1, self.tetro_pos.y, &mut tetro);
-            self.tetro = Some(tetro);
+    /// Swaps the falling tetro with the held one, or with the next one if
+    /// nothing is held yet. Allowed once per tetro, and only if the incoming
+    /// tetro fits at the spawn position.
+    pub fn hold(&mut self) {
+        if !self.settings.hold || self.hold_used || self.tetro.is_none() {
+            return
+        }
+
+        self.fill_next();
+        let new_tetro = match self.hold.as_ref().or(self.next.front()) {
+            Some(tetro) => tetro.clone(),
+            None => return
+        };
+        let new_pos = self.spawn_pos(&new_tetro);
+        if !self.fits(new_pos.x, new_pos.y, &new_tetro) {
+            return
+        }
+
+        if self.hold.is_none() {
+            self.take_next();
         }
+        let held = self.tetro.replace(new_tetro).unwrap();
+        self.hold = Some(Tetro::new(held.kind));
+        self.tetro_pos = new_pos;
+        self.hold_used = true;
+        self.gravity = 0.0;
+        self.last_kick = None;
+        self.reset_lock();
+    }
+
+    pub fn move_left(&mut self) -> bool {
+        self.move_by(-1)
+    }
+
+    pub fn move_right(&mut self) -> bool {
+        self.move_by(1)
+    }
+
+    /// Moves the tetro `dx` columns sideways if it fits there.
+    fn move_by(&mut self, dx: i32) -> bool {
+        if let Some(tetro) = &self.tetro {
+            let Pos { x, y } = self.tetro_pos;
+            if self.fits(x + dx, y, tetro) {
+                self.tetro_pos = Pos::new(x + dx, y);
+                self.last_kick = None;
+                self.on_shift();
+                return true
+            }
+        }
+        false
     }
 
     pub fn rotate_right(&mut self) {
-        if let Some(mut tetro) = self.tetro.take() {
-            let new_tetro = tetro.rotate_right();
-            let size = tetro.size();
-            self.move_to(self.tetro_pos.x + size.mid_x() - 1, self.tetro_pos.y + size.mid_y() - 1, &mut tetro);
-            self.tetro = Some(new_tetro);
-        }
-    }
-    pub fn descend_soft(&mut self) {
-        if let Some(mut tetro) = self.tetro.take() {
-            match tetro.state {
-                TetroState::Descent => {
-                    self.move_to(self.tetro_pos.x, self.tetro_pos.y + 1, &mut tetro);
-                    self.tetro = Some(tetro);
-                },
-                TetroState::WaitToPlace => {
-                    self.place(self.tetro_pos.x, self.tetro_pos.y, &tetro);
+        if let Some(tetro) = &self.tetro {
+            self.rotate(tetro.orientation.cw());
+        }
+    }
+
+    pub fn rotate_left(&mut self) {
+        if let Some(tetro) = &self.tetro {
+            self.rotate(tetro.orientation.ccw());
+        }
+    }
+
+    pub fn rotate_180(&mut self) {
+        if let Some(tetro) = &self.tetro {
+            self.rotate(tetro.orientation.flip());
+        }
+    }
+
+    /// Rotates the tetro with SRS, trying each kick in order. The rotation is
+    /// rejected if none of them fits.
+    fn rotate(&mut self, to: Orientation) {
+        if let Some(tetro) = self.tetro.take() {
+            let rotated = tetro.rotated(to);
+            let (from_x, from_y) = srs::offset(tetro.kind, tetro.orientation);
+            let (to_x, to_y) = srs::offset(tetro.kind, to);
+            let base_x = self.tetro_pos.x - from_x + to_x;
+            let base_y = self.tetro_pos.y - from_y + to_y;
+
+            for (i, (kick_x, kick_y)) in srs::kicks(tetro.kind, tetro.orientation, to).iter().enumerate() {
+                // kicks point up, rows count down
+                let (x, y) = (base_x + kick_x, base_y - kick_y);
+                if self.fits(x, y, &rotated) {
+                    self.tetro_pos = Pos::new(x, y);
+                    self.tetro = Some(rotated);
+                    self.last_kick = Some(i);
+                    self.last_flip = to == tetro.orientation.flip();
+                    self.on_shift();
+                    return;
                 }
             }
-            self.tick_count = 0; // initialize tick_count
+
+            self.tetro = Some(tetro);
         }
     }
 
+    /// Moves the tetro down a row and restarts gravity. Returns `false` if it
+    /// rests on the stack, in which case the lock delay decides when it locks.
+    pub fn descend_soft(&mut self) -> bool {
+        self.gravity = 0.0;
+        self.fall()
+    }
+
+    fn fall(&mut self) -> bool {
+        if let Some(tetro) = &self.tetro {
+            let Pos { x, y } = self.tetro_pos;
+            if self.fits(x, y + 1, tetro) {
+                self.tetro_pos = Pos::new(x, y + 1);
+                self.last_kick = None;
+                self.on_descend();
+                return true
+            }
+        }
+        false
+    }
+
+    /// Drops the tetro onto the stack and locks it at once. The next tetro
+    /// spawns on the following tick.
     pub fn descend_hard(&mut self) {
-        if let Some(mut tetro) = self.tetro.take() {
+        if let Some(tetro) = self.tetro.take() {
+            let Pos { x, y } = self.tetro_pos;
+            let landing_y = self.landing_y(x, y, &tetro);
+            self.award((landing_y - y) as u32 * HARD_DROP_POINTS);
+            if landing_y > y {
+                self.last_kick = None;
+            }
+            self.tetro_pos = Pos::new(x, landing_y);
             self.tetro = Some(tetro);
+            self.lock();
+            self.gravity = 0.0;
         }
     }
 
-    fn place(&mut self, x: u16, y: u16, tetro: &Tetro) {
-        let Size { width, height } = tetro.buffer.size();
-        let Pos { x: tetro_x, y: tetro_y } = self.tetro_pos;
-        for y in 0..height {
-            for x in 0..width {
-                if let Some(color) = tetro.buffer.get(x, y) {
-                    self.buffer.set(x + tetro_x, y + tetro_y, Some(color));
-                }
+    /// Locks the falling tetro into the stack where it is. Completed lines
+    /// are cleared after the line clear delay, then the next tetro spawns
+    /// after the entry delay, unless the lock topped out.
+    fn lock(&mut self) {
+        if let Some(tetro) = self.tetro.take() {
+            let Pos { x, y } = self.tetro_pos;
+            let spin = self.spin(&tetro);
+            self.place(x, y, &tetro);
+            self.pieces += 1;
+
+            // lock out, tetro buffers have no empty rows
+            if y + tetro.size().height as i32 <= 0 {
+                self.on_top_out(TopOut::LockOut);
+            } else if y < 0 && self.settings.partial_lock_out {
+                self.on_top_out(TopOut::PartialLockOut);
+            }
+
+            let completed = (0..self.buffer.height()).any(|row| self.buffer.line_completed(row));
+            if completed && self.settings.line_clear_delay > 0 {
+                self.phase = Phase::LineClear { frames: self.settings.line_clear_delay, spin };
+            } else {
+                self.clear_lines(spin);
             }
         }
     }
 
-    fn move_to(&mut self, x: u16, y: u16, tetro: &mut Tetro) {
-        let pl_size = self.size();
-        let size = tetro.size();
-        if (x + size.width) > pl_size.width { // over right bound
-            self.tetro_pos = Pos::new(x - 1, y);
-            // self.debug_msg = format!("{:?}", self.tetro_pos);
-            tetro.state = TetroState::Descent;
-        } else if (y + size.height) > pl_size.height { // over bottom bound
-            self.tetro_pos = Pos::new(x, y - 1);
-            // self.debug_msg = format!("{:?}", self.tetro_pos);
-            tetro.state = TetroState::WaitToPlace;
-        } else if self.is_reach_bottom(x, y, &tetro) { // reach bottom
-            self.tetro_pos = Pos::new(x, y - 1);
-            // self.debug_msg = format!("{:?}", self.tetro_pos);
-            tetro.state = TetroState::WaitToPlace;
-        } else { // in bound
-            self.tetro_pos = Pos::new(x, y);
-            // self.debug_msg = format!("{:?}", self.tetro_pos);
-            tetro.state = TetroState::Descent;
+    /// Removes and scores completed lines, then waits for the entry delay.
+    fn clear_lines(&mut self, spin: Spin) {
+        let lines = self.remove_line_completed();
+        self.score_clear(lines, spin);
+        self.phase = Phase::Entry(self.settings.are);
+
+        let completed = match self.settings.mode {
+            Mode::Marathon if !self.settings.endless => self.lines >= self.settings.marathon_lines(),
+            Mode::Sprint => self.lines >= self.settings.sprint_lines,
+            Mode::Cheese => self.garbage_lines >= self.settings.cheese_lines,
+            _ => false
+        };
+        if completed {
+            self.completed = true;
+        }
+        self.fill_garbage();
+    }
+
+    /// Pushes garbage rows in from the bottom in [`Mode::Cheese`] until
+    /// [`Settings::cheese_rows`] are on the board, or all the garbage lines
+    /// left to clear.
+    fn fill_garbage(&mut self) {
+        if self.settings.mode != Mode::Cheese {
+            return
+        }
+
+        let on_board = self.buffer.rows().iter()
+            .filter(|row| row.contains(&Some(Block::Garbage)))
+            .count() as u32;
+        let left = self.settings.cheese_lines.saturating_sub(self.garbage_lines);
+        let rows = (self.settings.cheese_rows.min(self.size().height) as u32).min(left);
+        for _ in on_board..rows {
+            let line = self.garbage_line();
+            self.buffer.remove_and_append_line(line);
+        }
+    }
+
+    /// A full row of garbage but for one hole.
+    fn garbage_line(&mut self) -> Vec<Cell> {
+        let width = self.size().width;
+        let hole = match self.garbage_hole {
+            Some(hole) if self.garbage_rng.gen_range(0..100) >= self.settings.messiness => hole,
+            _ => self.garbage_rng.gen_range(0..width)
+        };
+        self.garbage_hole = Some(hole);
+
+        let mut line = vec![Some(Block::Garbage); width as usize];
+        line[hole as usize] = None;
+        line
+    }
+
+    /// Spawns the next tetro and applies the inputs buffered meanwhile.
+    /// Returns `false` on a block out.
+    fn spawn(&mut self) -> bool {
+        let new_tetro = self.take_next();
+        let new_pos = self.spawn_pos(&new_tetro);
+
+        // block out, unless the mode clears room for the tetro
+        let fits = self.fits(new_pos.x, new_pos.y, &new_tetro)
+            || (self.on_top_out(TopOut::BlockOut) && self.fits(new_pos.x, new_pos.y, &new_tetro));
+        if !fits {
+            self.place(new_pos.x, new_pos.y, &new_tetro);
+            self.top_out = Some(TopOut::BlockOut);
+            return false;
+        }
+
+        self.tetro_pos = new_pos;
+        self.tetro = Some(new_tetro);
+        self.phase = Phase::Falling;
+        self.hold_used = false;
+        self.last_kick = None;
+        self.reset_lock();
+        for input in std::mem::take(&mut self.buffered) {
+            self.on_input(input);
+        }
+        true
+    }
+
+    /// Adds `points` to the score, in every mode but zen, which keeps none.
+    fn award(&mut self, points: u32) {
+        if self.settings.mode != Mode::Zen {
+            self.score += points;
         }
     }
 
-    fn remove_line_completed(&mut self) -> bool {
+    /// Applies the top out policy of the mode. Zen clears the top of the
+    /// stack, takes a line off for every visible row cleared and plays on;
+    /// every other mode ends. Returns whether the game goes on.
+    fn on_top_out(&mut self, top_out: TopOut) -> bool {
+        if self.settings.mode != Mode::Zen {
+            self.top_out = Some(top_out);
+            return false
+        }
+
+        let visible_rows = match self.settings.zen_clear {
+            ZenClear::Top => self.settings.zen_clear_rows.min(self.size().height),
+            ZenClear::Board => self.size().height
+        };
+        for y in 0..self.hidden_rows() + visible_rows {
+            self.buffer.clear_line(y);
+        }
+        self.lines = self.lines.saturating_sub(visible_rows as u32);
+        self.top_outs += 1;
+        true
+    }
+
+    /// T-spin by the 3-corner rule: a T whose last move was a rotation, with
+    /// three of the four corners around its center blocked. It is a mini
+    /// unless both corners it points at are blocked or a quarter turn used
+    /// its last kick, the TST kick.
+    fn spin(&self, tetro: &Tetro) -> Spin {
+        let kick = match self.last_kick {
+            Some(kick) if tetro.kind == Kind::T => kick,
+            _ => return Spin::None
+        };
+
+        // corners of the 3x3 rotation box
+        let (offset_x, offset_y) = srs::offset(tetro.kind, tetro.orientation);
+        let box_x = self.tetro_pos.x - offset_x;
+        let box_y = self.tetro_pos.y - offset_y;
+        let blocked = |cx: i32, cy: i32| self.is_blocked(box_x + cx, box_y + cy);
+        let (top_left, top_right) = (blocked(0, 0), blocked(2, 0));
+        let (bottom_left, bottom_right) = (blocked(0, 2), blocked(2, 2));
+
+        let corners = [top_left, top_right, bottom_left, bottom_right]
+            .iter().filter(|corner| **corner).count();
+        if corners < 3 {
+            return Spin::None
+        }
+
+        let front = match tetro.orientation {
+            Orientation::Zero => top_left && top_right,
+            Orientation::Right => top_right && bottom_right,
+            Orientation::Two => bottom_left && bottom_right,
+            Orientation::Left => top_left && bottom_left
+        };
+        if front || (kick == 4 && !self.last_flip) {
+            Spin::Full
+        } else {
+            Spin::Mini
+        }
+    }
+
+    /// Scores the lines cleared by the last locked tetro and updates the
+    /// back-to-back and combo streaks.
+    fn score_clear(&mut self, lines: u32, spin: Spin) {
+        let mut clear = Clear {
+            lines,
+            spin,
+            perfect: lines > 0 && self.buffer.rows().iter().flatten().all(|cell| cell.is_none()),
+            back_to_back: false,
+            combo: 0
+        };
+
+        if lines > 0 {
+            let difficult = clear.is_difficult();
+            clear.back_to_back = difficult && self.back_to_back;
+            self.back_to_back = difficult;
+            clear.combo = self.combo.map_or(0, |combo| combo + 1);
+            self.combo = Some(clear.combo);
+        } else {
+            self.combo = None;
+        }
+
+        self.award(self.settings.scoring.points(&clear, self.level));
+
+        // level up, zen keeps the start level and marathon stops at its last
+        self.lines += lines;
+        let level = self.settings.start_level + self.lines / self.settings.lines_per_level.max(1);
+        self.level = match self.settings.mode {
+            Mode::Zen => self.level,
+            Mode::Marathon if !self.settings.endless => level.min(self.settings.marathon_level.max(self.settings.start_level)),
+            _ => level
+        };
+
+        if lines > 0 || spin != Spin::None {
+            self.last_clear = Some(clear);
+        }
+    }
+
+    fn is_grounded(&self) -> bool {
+        let Pos { x, y } = self.tetro_pos;
+        self.tetro.as_ref().is_some_and(|tetro| !self.fits(x, y + 1, tetro))
+    }
+
+    fn reset_lock(&mut self) {
+        self.lock_frames = 0;
+        self.lock_resets = 0;
+        self.lowest_y = self.tetro_pos.y;
+    }
+
+    /// Updates the lock delay after the tetro fell a row. Reaching a new
+    /// lowest row restarts the timer and the reset count.
+    fn on_descend(&mut self) {
+        let new_lowest = self.tetro_pos.y > self.lowest_y;
+        if new_lowest {
+            self.lowest_y = self.tetro_pos.y;
+            self.lock_resets = 0;
+        }
+        if new_lowest || self.settings.lock_reset == LockReset::Step {
+            self.lock_frames = 0;
+        }
+    }
+
+    /// Updates the lock delay after a successful move or rotation.
+    fn on_shift(&mut self) {
+        if self.lock_frames == 0 {
+            return
+        }
+        match self.settings.lock_reset {
+            LockReset::Move => if self.lock_resets < MAX_LOCK_RESETS {
+                self.lock_resets += 1;
+                self.lock_frames = 0;
+            },
+            LockReset::Infinity => {
+                self.lock_frames = 0;
+            },
+            LockReset::Step => {}
+        }
+    }
+
+    /// Where the falling tetro would land with a hard drop.
+    pub fn ghost_pos(&self) -> Option<Pos> {
+        self.tetro.as_ref().map(|tetro| {
+            let Pos { x, y } = self.tetro_pos;
+            Pos::new(x, self.landing_y(x, y, tetro))
+        })
+    }
+
+    /// Lowest row a tetro at (`x`, `y`) can fall to without leaving the
+    /// board or overlapping the stack.
+    pub fn landing_y(&self, x: i32, y: i32, tetro: &Tetro) -> i32 {
+        let mut landing_y = y;
+        while self.fits(x, landing_y + 1, tetro) {
+            landing_y += 1;
+        }
+        landing_y
+    }
+
+    fn place(&mut self, x: i32, y: i32, tetro: &Tetro) {
+        let Size { width, height } = tetro.buffer.size();
+        let hidden = self.hidden_rows() as i32;
+        for ty in 0..height {
+            for tx in 0..width {
+                if let Some(block) = tetro.buffer.get(tx, ty) {
+                    let (bx, by) = (x + tx as i32, y + ty as i32 + hidden);
+                    self.buffer.set(bx as u16, by as u16, Some(block));
+                }
+            }
+        }
+    }
+
+    fn remove_line_completed(&mut self) -> u32 {
         let size = self.buffer.size();
         let mut y = 0;
-        let mut result = false;
 
         let mut completed_lines_count = 0;
         while y < size.height {
             if self.buffer.line_completed(y) {
+                if self.buffer.rows()[y as usize].contains(&Some(Block::Garbage)) {
+                    self.garbage_lines += 1;
+                }
                 self.buffer.remove_and_prepend_line(y);
                 completed_lines_count += 1;
-                result = true;
             } else {
                 y += 1;
             }
         }
 
-        // add score. 10 -> 30 -> 60 -> 100
-        if completed_lines_count > 0 {
-            self.score += (completed_lines_count * (completed_lines_count + 1)) / 2 * 10;
-        }
-
-        result
+        completed_lines_count
     }
 
-    fn is_reach_bottom(&self, pos_x: u16, pos_y: u16, tetro: &Tetro) -> bool {
-        let Size { width, height } = tetro.buffer.size();
+    /// Whether a tetro at (`x`, `y`) lies inside the board without
+    /// overlapping the stack. Every move, rotation and drop goes through
+    /// this check.
+    pub fn fits(&self, x: i32, y: i32, tetro: &Tetro) -> bool {
+        let Size { width, height } = tetro.size();
         for ty in 0..height {
             for tx in 0..width {
-                if let Some(_) = tetro.buffer.get(tx, ty) {
-                    if self.buffer.get(pos_x + tx, pos_y + ty).is_some() {
-                        return true
-                    }
+                if tetro.buffer.get(tx, ty).is_some() && self.is_blocked(x + tx as i32, y + ty as i32) {
+                    return false
                 }
             }
         }
-        false
+        true
     }
-}
\ No newline at end of file
+
+    /// Whether a cell is outside the board, hidden rows included, or taken
+    /// by the stack.
+    fn is_blocked(&self, x: i32, y: i32) -> bool {
+        let size = self.size();
+        let hidden = self.hidden_rows() as i32;
+        if x < 0 || y < -hidden || x >= size.width as i32 || y >= size.height as i32 {
+            return true
+        }
+        self.buffer.get(x as u16, (y + hidden) as u16).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Rules;
+
+    fn playground_with(kind: Kind, orientation: Orientation, x: i32, y: i32) -> Playground {
+        let mut playground = Playground::new(Settings::default(), 0);
+        playground.tetro = Some(Tetro::new(kind).rotated(orientation));
+        playground.tetro_pos = Pos::new(x, y);
+        playground
+    }
+
+    /// Columns of the holes in the garbage rows, top to bottom.
+    fn garbage_holes(playground: &Playground) -> Vec<usize> {
+        playground.buffer.rows().iter()
+            .filter(|row| row.contains(&Some(Block::Garbage)))
+            .map(|row| {
+                assert_eq!(row.iter().filter(|cell| cell.is_none()).count(), 1);
+                row.iter().position(|cell| cell.is_none()).unwrap()
+            })
+            .collect()
+    }
+
+    /// Plugs the hole in the bottom row and clears it.
+    fn dig(playground: &mut Playground) {
+        let bottom = playground.buffer.height() - 1;
+        let hole = *garbage_holes(playground).last().unwrap();
+        playground.buffer.set(hole as u16, bottom, Some(Block::Garbage));
+        playground.clear_lines(Spin::None);
+    }
+
+    /// A T resting on the floor with a fresh lock delay.
+    fn grounded_with(lock_reset: LockReset) -> Playground {
+        let settings = Settings { lock_reset, ..Settings::default() };
+        let mut playground = Playground::new(settings, 0);
+        playground.tetro = Some(Tetro::new(Kind::T));
+        playground.tetro_pos = Pos::new(3, 0);
+        while playground.fall() {}
+        playground.reset_lock();
+        playground.phase = Phase::Falling;
+        playground
+    }
+
+    /// Ticks while shifting the tetro back and forth and returns how many
+    /// ticks it took to lock, or `None` if it was still falling after `max`.
+    fn ticks_to_lock(playground: &mut Playground, max: u32) -> Option<u32> {
+        for tick in 1..=max {
+            playground.on_tick();
+            if playground.tetro.is_none() {
+                return Some(tick);
+            }
+            if tick % 2 == 0 {
+                playground.move_left();
+            } else {
+                playground.move_right();
+            }
+        }
+        None
+    }
+
+    /// Fills the cells at visible (`x`, `y`) with garbage.
+    fn fill(playground: &mut Playground, cells: impl IntoIterator<Item = (u16, u16)>) {
+        let hidden = playground.hidden_rows();
+        for (x, y) in cells {
+            playground.buffer.set(x, y + hidden, Some(Block::Garbage));
+        }
+    }
+
+    #[test]
+    fn four_turns_come_back_to_the_start() {
+        for kind in Kind::ALL {
+            let mut playground = playground_with(kind, Orientation::Zero, 3, 5);
+            for _ in 0..4 {
+                playground.rotate_right();
+            }
+            assert_eq!(playground.tetro_pos, Pos::new(3, 5), "{:?}", kind);
+            assert_eq!(playground.last_kick, Some(0));
+        }
+    }
+
+    #[test]
+    fn rotation_kicks_off_the_wall() {
+        // the T points right with its stem against the left wall, turning
+        // it down needs the column left of the wall
+        let mut playground = playground_with(Kind::T, Orientation::Right, 0, 5);
+        playground.rotate_right();
+        assert_eq!(playground.tetro.as_ref().map(|tetro| tetro.orientation), Some(Orientation::Two));
+        assert_eq!(playground.tetro_pos, Pos::new(0, 6));
+        assert_eq!(playground.last_kick, Some(1));
+    }
+
+    #[test]
+    fn rotation_that_fits_nowhere_is_rejected() {
+        // an I lying on the floor in a one row high well
+        let mut playground = playground_with(Kind::I, Orientation::Zero, 3, 19);
+        let hidden = playground.hidden_rows();
+        for x in 0..10 {
+            playground.buffer.set(x, 18 + hidden, Some(Block::Garbage));
+        }
+        playground.rotate_right();
+        assert_eq!(playground.tetro.as_ref().map(|tetro| tetro.orientation), Some(Orientation::Zero));
+        assert_eq!(playground.tetro_pos, Pos::new(3, 19));
+    }
+
+    #[test]
+    fn t_spin_double_is_a_full_spin() {
+        // the T points down into a slot under an overhang on its right
+        let mut playground = playground_with(Kind::T, Orientation::Two, 1, 18);
+        fill(&mut playground, (0..10).filter(|x| !(1..=3).contains(x)).map(|x| (x, 18)));
+        fill(&mut playground, (0..10).filter(|x| *x != 2).map(|x| (x, 19)));
+        fill(&mut playground, [(3, 17)]);
+        playground.last_kick = Some(0);
+        playground.lock();
+
+        let clear = playground.last_clear.expect("a clear");
+        assert_eq!((clear.lines, clear.spin), (2, Spin::Full));
+    }
+
+    #[test]
+    fn t_spin_mini_needs_the_tst_kick_of_a_quarter_turn() {
+        // the T points up with only its top right corner blocked, besides
+        // the floor under it
+        let mut playground = playground_with(Kind::T, Orientation::Zero, 0, 18);
+        fill(&mut playground, [(2, 18)]);
+        let tetro = playground.tetro.clone().unwrap();
+
+        playground.last_kick = None;
+        assert_eq!(playground.spin(&tetro), Spin::None);
+        playground.last_kick = Some(0);
+        assert_eq!(playground.spin(&tetro), Spin::Mini);
+        playground.last_kick = Some(4);
+        assert_eq!(playground.spin(&tetro), Spin::Full);
+        playground.last_flip = true;
+        assert_eq!(playground.spin(&tetro), Spin::Mini);
+    }
+
+    #[test]
+    fn delays_buffer_taps_but_not_soft_drops() {
+        let settings = Rules::Classic.settings();
+        let mut playground = Playground::new(settings, 0);
+        playground.on_tick();
+        playground.descend_hard();
+        let score = playground.score;
+        assert_eq!(playground.phase, Phase::Entry(10));
+
+        playground.on_input(Input::RotateRight);
+        let mut ticks = 0;
+        while playground.phase != Phase::Falling {
+            playground.on_input(Input::SoftDrop);
+            playground.on_tick();
+            ticks += 1;
+        }
+        assert_eq!(ticks, 10);
+        let tetro = playground.tetro.as_ref().unwrap();
+        assert_eq!(tetro.orientation, Orientation::Right);
+        assert!(playground.tetro_pos.y < 0);
+        assert_eq!(playground.score, score);
+    }
+
+    #[test]
+    fn marathon_ends_at_its_last_level() {
+        for start_level in [1, 10] {
+            let settings = Settings { start_level, ..Settings::default() };
+            let goal = settings.marathon_lines();
+            let mut playground = Playground::new(settings, 0);
+            for _ in 0..goal {
+                assert!(!playground.completed);
+                playground.score_clear(1, Spin::None);
+                playground.clear_lines(Spin::None);
+            }
+            assert!(playground.completed);
+            assert_eq!(playground.level, 15);
+        }
+    }
+
+    #[test]
+    fn zen_clears_the_top_on_top_out_and_keeps_no_score() {
+        let settings = Settings { mode: Mode::Zen, ..Settings::default() };
+        let mut playground = Playground::new(settings, 0);
+        playground.lines = 25;
+        fill(&mut playground, (0..20).flat_map(|y| (1..10).map(move |x| (x, y))));
+        // the stack reaches the top, so the first tetro locks out
+        playground.on_tick();
+        playground.on_input(Input::HardDrop);
+        assert_eq!(playground.top_out, None);
+        assert!(playground.on_tick());
+        assert_eq!(playground.top_outs, 1);
+        assert_eq!(playground.lines, 15);
+        assert_eq!(playground.score, 0);
+    }
+
+    #[test]
+    fn move_reset_stops_after_fifteen_shifts() {
+        let mut playground = grounded_with(LockReset::Move);
+        let delay = playground.settings.lock_delay;
+        // each of the first 15 shifts restarts the timer after a single tick
+        assert_eq!(ticks_to_lock(&mut playground, 15), None);
+        assert_eq!(playground.lock_resets, MAX_LOCK_RESETS);
+        assert_eq!(ticks_to_lock(&mut playground, delay), Some(delay));
+    }
+
+    #[test]
+    fn new_lowest_row_clears_the_reset_count() {
+        let mut playground = grounded_with(LockReset::Move);
+        let floor = playground.tetro_pos.y;
+        playground.tetro_pos.y = floor - 2;
+        playground.lowest_y = floor - 1;
+        playground.lock_resets = MAX_LOCK_RESETS;
+        playground.lock_frames = 5;
+        // falling back to a row already reached keeps the count
+        playground.descend_soft();
+        assert_eq!(playground.lock_resets, MAX_LOCK_RESETS);
+        assert_eq!(playground.lock_frames, 5);
+        playground.descend_soft();
+        assert_eq!(playground.lock_resets, 0);
+        assert_eq!(playground.lock_frames, 0);
+    }
+
+    #[test]
+    fn step_reset_ignores_shifts() {
+        let mut playground = grounded_with(LockReset::Step);
+        let delay = playground.settings.lock_delay;
+        assert_eq!(ticks_to_lock(&mut playground, delay), Some(delay));
+    }
+
+    #[test]
+    fn infinity_never_locks_while_the_tetro_moves() {
+        let mut playground = grounded_with(LockReset::Infinity);
+        assert_eq!(ticks_to_lock(&mut playground, 1000), None);
+        assert_eq!(playground.pieces, 0);
+    }
+
+    #[test]
+    fn spawning_into_the_stack_blocks_out() {
+        let mut playground = Playground::new(Settings::default(), 0);
+        let Size { width, height } = playground.buffer.size();
+        for y in 0..height {
+            for x in 1..width {
+                playground.buffer.set(x, y, Some(Block::Garbage));
+            }
+        }
+        assert!(!playground.on_tick());
+        assert_eq!(playground.top_out, Some(TopOut::BlockOut));
+        assert!(playground.tetro.is_none());
+    }
+
+    #[test]
+    fn locking_above_the_field_locks_out() {
+        let mut playground = playground_with(Kind::T, Orientation::Zero, 3, -2);
+        playground.lock();
+        assert_eq!(playground.top_out, Some(TopOut::LockOut));
+        assert!(!playground.on_tick());
+    }
+
+    #[test]
+    fn locking_partly_above_the_field_needs_partial_lock_out() {
+        let mut playground = playground_with(Kind::T, Orientation::Zero, 3, -1);
+        playground.lock();
+        assert_eq!(playground.top_out, None);
+
+        let mut playground = playground_with(Kind::T, Orientation::Zero, 3, -1);
+        playground.settings.partial_lock_out = true;
+        playground.lock();
+        assert_eq!(playground.top_out, Some(TopOut::PartialLockOut));
+    }
+
+    #[test]
+    fn cheese_starts_with_its_garbage_rows() {
+        let settings = Settings { mode: Mode::Cheese, ..Settings::default() };
+        let playground = Playground::new(settings, 0);
+        assert_eq!(garbage_holes(&playground).len(), 10);
+        let bottom = &playground.buffer.rows()[playground.buffer.height() as usize - 10..];
+        assert!(bottom.iter().all(|row| row.contains(&Some(Block::Garbage))));
+    }
+
+    #[test]
+    fn cheese_tops_up_only_the_lines_left() {
+        let settings = Settings { mode: Mode::Cheese, cheese_lines: 14, ..Settings::default() };
+        let mut playground = Playground::new(settings, 0);
+        for dug in 1..=14 {
+            dig(&mut playground);
+            assert_eq!(playground.garbage_lines, dug);
+            assert_eq!(garbage_holes(&playground).len() as u32, (14 - dug).min(10));
+        }
+    }
+
+    #[test]
+    fn cheese_without_messiness_lines_the_holes_up() {
+        let settings = Settings { mode: Mode::Cheese, messiness: 0, ..Settings::default() };
+        let mut playground = Playground::new(settings, 0);
+        for _ in 0..5 {
+            let holes = garbage_holes(&playground);
+            assert!(holes.iter().all(|&hole| hole == holes[0]));
+            dig(&mut playground);
+        }
+    }
+
+    #[test]
+    fn cheese_finishes_once_its_lines_are_dug() {
+        let settings = Settings { mode: Mode::Cheese, ..Settings::default() };
+        let mut playground = Playground::new(settings, 0);
+        for _ in 0..playground.settings.cheese_lines {
+            assert!(!playground.completed);
+            dig(&mut playground);
+        }
+        assert!(playground.completed);
+        assert!(!playground.on_tick());
+    }
+}
@@ -0,0 +1,224 @@
+use std::{fmt::{self, Debug, Display, Formatter}, str::FromStr};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use crate::tetro::Kind;
+
+/// Source of the tetro sequence.
+///
+/// Implementations must be fully determined by the seed they were built
+/// from, so that the same seed always deals the same pieces.
+pub trait Randomizer: Debug {
+    fn next_kind(&mut self) -> Kind;
+}
+
+/// Selectable piece generators.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Generator {
+    /// Uniformly random, with replacement.
+    Random,
+    /// Guideline 7-bag.
+    #[default]
+    Bag,
+    /// TGM style, rerolls against the last 4 pieces.
+    History,
+    /// Classic NES, rerolls once on a repeat.
+    Nes
+}
+
+impl Generator {
+    pub const ALL: [Generator; 4] = [Generator::Random, Generator::Bag, Generator::History, Generator::Nes];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Generator::Random => "random",
+            Generator::Bag => "bag",
+            Generator::History => "history",
+            Generator::Nes => "nes"
+        }
+    }
+
+    pub fn randomizer(&self, seed: u64) -> Box<dyn Randomizer> {
+        match self {
+            Generator::Random => Box::new(Random::new(seed)),
+            Generator::Bag => Box::new(Bag::new(seed)),
+            Generator::History => Box::new(History::new(seed)),
+            Generator::Nes => Box::new(Nes::new(seed))
+        }
+    }
+}
+
+impl Display for Generator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Generator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Generator::ALL.iter()
+            .find(|generator| generator.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown generator: {}", s))
+    }
+}
+
+/// Picks each tetro uniformly at random, with replacement.
+#[derive(Debug)]
+pub struct Random {
+    rng: StdRng
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random {
+            rng: StdRng::seed_from_u64(seed)
+        }
+    }
+}
+
+impl Randomizer for Random {
+    fn next_kind(&mut self) -> Kind {
+        Kind::ALL[self.rng.gen_range(0..Kind::ALL.len())]
+    }
+}
+
+/// Deals all seven tetros in a shuffled order before refilling the bag.
+#[derive(Debug)]
+pub struct Bag {
+    rng: StdRng,
+    bag: Vec<Kind>
+}
+
+impl Bag {
+    pub fn new(seed: u64) -> Bag {
+        Bag {
+            rng: StdRng::seed_from_u64(seed),
+            bag: Vec::with_capacity(Kind::ALL.len())
+        }
+    }
+}
+
+impl Randomizer for Bag {
+    fn next_kind(&mut self) -> Kind {
+        if self.bag.is_empty() {
+            self.bag.extend_from_slice(&Kind::ALL);
+            self.bag.shuffle(&mut self.rng);
+        }
+        self.bag.pop().unwrap()
+    }
+}
+
+/// Rolls up to `ROLLS` times for a tetro that is not in the history of the
+/// last four, keeping the last roll if all of them hit. The history starts
+/// as Z S S Z and the first tetro is never S, Z or O, as in TGM2.
+#[derive(Debug)]
+pub struct History {
+    rng: StdRng,
+    history: [Kind; 4],
+    first: bool
+}
+
+impl History {
+    const ROLLS: u32 = 6;
+
+    pub fn new(seed: u64) -> History {
+        History {
+            rng: StdRng::seed_from_u64(seed),
+            history: [Kind::Z, Kind::S, Kind::S, Kind::Z],
+            first: true
+        }
+    }
+
+    fn roll(&mut self) -> Kind {
+        Kind::ALL[self.rng.gen_range(0..Kind::ALL.len())]
+    }
+}
+
+impl Randomizer for History {
+    fn next_kind(&mut self) -> Kind {
+        let kind = if self.first {
+            self.first = false;
+            const FIRST: [Kind; 4] = [Kind::I, Kind::J, Kind::L, Kind::T];
+            FIRST[self.rng.gen_range(0..FIRST.len())]
+        } else {
+            let mut kind = self.roll();
+            for _ in 1..History::ROLLS {
+                if !self.history.contains(&kind) {
+                    break
+                }
+                kind = self.roll();
+            }
+            kind
+        };
+
+        self.history.rotate_right(1);
+        self.history[0] = kind;
+        kind
+    }
+}
+
+/// Rolls one of eight slots, where the eighth slot or a repeat of the last
+/// tetro triggers a single reroll over the seven tetros.
+#[derive(Debug)]
+pub struct Nes {
+    rng: StdRng,
+    last: Option<Kind>
+}
+
+impl Nes {
+    pub fn new(seed: u64) -> Nes {
+        Nes {
+            rng: StdRng::seed_from_u64(seed),
+            last: None
+        }
+    }
+}
+
+impl Randomizer for Nes {
+    fn next_kind(&mut self) -> Kind {
+        let i = self.rng.gen_range(0..Kind::ALL.len() + 1);
+        let kind = match Kind::ALL.get(i) {
+            Some(kind) if Some(*kind) != self.last => *kind,
+            _ => Kind::ALL[self.rng.gen_range(0..Kind::ALL.len())]
+        };
+        self.last = Some(kind);
+        kind
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bag_deals_each_tetro_once_per_seven() {
+        for seed in 0..20 {
+            let mut bag = Bag::new(seed);
+            for _ in 0..10 {
+                let mut kinds = (0..Kind::ALL.len()).map(|_| bag.next_kind()).collect::<Vec<Kind>>();
+                kinds.sort_by_key(|kind| Kind::ALL.iter().position(|k| k == kind));
+                assert_eq!(kinds, Kind::ALL);
+            }
+        }
+    }
+
+    #[test]
+    fn history_never_starts_with_s_z_or_o() {
+        for seed in 0..100 {
+            let first = History::new(seed).next_kind();
+            assert!(![Kind::S, Kind::Z, Kind::O].contains(&first));
+        }
+    }
+
+    #[test]
+    fn generators_follow_their_seed() {
+        for generator in Generator::ALL {
+            let deal = |seed| {
+                let mut randomizer = generator.randomizer(seed);
+                (0..50).map(|_| randomizer.next_kind()).collect::<Vec<Kind>>()
+            };
+            assert_eq!(deal(7), deal(7));
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::{collections::BTreeMap, env, fs, io, path::PathBuf};
+use tetris::Settings;
+
+/// Personal bests, kept in a text file of `<name> <value>` lines.
+#[derive(Debug, Default)]
+pub struct Records {
+    /// Where the records are saved. Nothing is saved if unset.
+    pub path: Option<PathBuf>,
+    values: BTreeMap<String, u64>
+}
+
+impl Records {
+    /// Name of a record of `mode` played with `settings`. Everything that
+    /// changes how a game plays is part of it, so that games played by
+    /// different rules are recorded apart.
+    pub fn name(mode: &str, settings: &Settings) -> String {
+        // FNV-1a of the gravity table, stable across builds
+        let gravity = settings.gravity.iter()
+            .fold(0xcbf2_9ce4_8422_2325u64, |hash, frames| (hash ^ frames.to_bits()).wrapping_mul(0x100_0000_01b3));
+        let size = settings.size();
+        format!("{}/{}/{}x{}+{}/{}/next{}/lock{}-{}/are{}-{}/level{}-{}/{}/{:x}{}",
+                mode, settings.generator, size.width, size.height, settings.hidden_rows(),
+                if settings.hold { "hold" } else { "no-hold" }, settings.next_count,
+                settings.lock_delay, settings.lock_reset, settings.are, settings.line_clear_delay,
+                settings.start_level, settings.lines_per_level, settings.scoring, gravity,
+                if settings.partial_lock_out { "/partial-lock-out" } else { "" })
+    }
+
+    pub fn get(&self, name: &str) -> Option<u64> {
+        self.values.get(name).copied()
+    }
+
+    /// Stores `value` under `name` if there is no record yet or it is
+    /// higher. Returns whether it was stored.
+    pub fn update(&mut self, name: &str, value: u64) -> bool {
+        if self.get(name).is_some_and(|best| best >= value) {
+            return false
+        }
+        self.values.insert(name.to_string(), value);
+        true
+    }
+
+    /// Stores `value` under `name` if there is no record yet or it is
+    /// lower, as for times. Returns whether it was stored.
+    pub fn update_lowest(&mut self, name: &str, value: u64) -> bool {
+        if self.get(name).is_some_and(|best| best <= value) {
+            return false
+        }
+        self.values.insert(name.to_string(), value);
+        true
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let lines = self.values.iter()
+                .map(|(name, value)| format!("{} {}\n", name, value))
+                .collect::<String>();
+            fs::write(path, lines)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the records at `path`. A missing or unreadable file, or lines
+    /// that do not parse, count as no records.
+    pub fn load(path: Option<PathBuf>) -> Records {
+        let values = path.as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|records| records.lines()
+                .filter_map(|line| {
+                    let (name, value) = line.split_once(' ')?;
+                    Some((name.to_string(), value.trim().parse::<u64>().ok()?))
+                })
+                .collect())
+            .unwrap_or_default();
+        Records { path, values }
+    }
+
+    /// `$XDG_DATA_HOME/tetris/records`, or `~/.local/share/tetris/records`.
+    pub fn default_path() -> Option<PathBuf> {
+        let data = env::var_os("XDG_DATA_HOME").map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+        Some(data.join("tetris").join("records"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tetris::{Generator, Rules};
+
+    #[test]
+    fn rules_are_recorded_apart() {
+        let modern = Records::name("ultra-7200", &Rules::Modern.settings());
+        let classic = Records::name("ultra-7200", &Rules::Classic.settings());
+        let random = Records::name("ultra-7200", &Settings { generator: Generator::Random, ..Settings::default() });
+        assert_ne!(modern, classic);
+        assert_ne!(modern, random);
+        assert!(!modern.contains(char::is_whitespace));
+    }
+}
@@ -0,0 +1,116 @@
+use std::{fmt::{self, Display, Formatter}, str::FromStr};
+
+/// T-spin recognised when a T locks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Spin {
+    None,
+    Mini,
+    Full
+}
+
+/// What a locked tetro cleared, with the streaks it was part of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Clear {
+    pub lines: u32,
+    pub spin: Spin,
+    /// The board is empty afterwards.
+    pub perfect: bool,
+    /// Follows another difficult clear with no easy clear in between.
+    pub back_to_back: bool,
+    /// Clearing locks in a row before this one. Only counts if lines > 0.
+    pub combo: u32
+}
+
+impl Clear {
+    /// Tetrises and spins that clear lines, which keep back-to-back alive.
+    pub fn is_difficult(&self) -> bool {
+        self.lines >= 4 || (self.spin != Spin::None && self.lines > 0)
+    }
+}
+
+/// Selectable scoring tables.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scoring {
+    /// Guideline: T-spins, back-to-back, combos and perfect clears, times
+    /// the level.
+    #[default]
+    Guideline,
+    /// NES: 40/100/300/1200 times the level. Level 1 is NES level 0.
+    Nes,
+    /// 10/30/60/100, the original scoring of this game.
+    Classic
+}
+
+impl Scoring {
+    pub const ALL: [Scoring; 3] = [Scoring::Guideline, Scoring::Nes, Scoring::Classic];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scoring::Guideline => "guideline",
+            Scoring::Nes => "nes",
+            Scoring::Classic => "classic"
+        }
+    }
+
+    /// Points for `clear` at `level`.
+    pub fn points(&self, clear: &Clear, level: u32) -> u32 {
+        match self {
+            Scoring::Guideline => {
+                let mut points = match (clear.spin, clear.lines) {
+                    (Spin::None, 0) => 0,
+                    (Spin::None, 1) => 100,
+                    (Spin::None, 2) => 300,
+                    (Spin::None, 3) => 500,
+                    (Spin::None, _) => 800,
+                    (Spin::Mini, 0) => 100,
+                    (Spin::Mini, 1) => 200,
+                    (Spin::Mini, _) => 400,
+                    (Spin::Full, 0) => 400,
+                    (Spin::Full, 1) => 800,
+                    (Spin::Full, 2) => 1200,
+                    (Spin::Full, _) => 1600
+                };
+                if clear.back_to_back {
+                    points = points * 3 / 2;
+                }
+                if clear.lines > 0 {
+                    points += 50 * clear.combo;
+                }
+                if clear.perfect {
+                    points += match clear.lines {
+                        1 => 800,
+                        2 => 1200,
+                        3 => 1800,
+                        _ if clear.back_to_back => 3200,
+                        _ => 2000
+                    };
+                }
+                points * level
+            },
+            Scoring::Nes => {
+                const POINTS: [u32; 5] = [0, 40, 100, 300, 1200];
+                POINTS[clear.lines.min(4) as usize] * level
+            },
+            Scoring::Classic => {
+                (clear.lines * (clear.lines + 1)) / 2 * 10
+            }
+        }
+    }
+}
+
+impl Display for Scoring {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Scoring {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Scoring::ALL.iter()
+            .find(|scoring| scoring.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown scoring: {}", s))
+    }
+}
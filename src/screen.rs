@@ -1,110 +1,226 @@
-use tui::{
-    backend::Backend,
-    terminal::{Terminal, Frame},
-    backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
+use ratatui::{
+    Frame,
+    layout::{Alignment, Rect},
     style::{Color, Style, Modifier},
-    text::{Span, Spans},
+    text::{Line, Span},
     widgets::{Borders, BorderType, Block, Paragraph},
 };
-use crossterm::{
-    execute,
-    event::{self, Event, KeyCode, KeyEvent},
-    terminal::{enable_raw_mode, disable_raw_mode, EnterAlternateScreen,
-               LeaveAlternateScreen}
-};
-use crate::{
-    playground::Playground,
+use std::time::{Duration, Instant};
+use crossterm::event::{KeyCode, KeyEvent};
+use crate::{args::Args, handling::Controller, keys::{Action, KeyMap}, records::Records};
+use tetris::{
+    Buffer,
+    Cell,
+    Game,
+    Mode,
+    Phase,
     tetro::Tetro,
     units::{Pos, Size}
 };
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum State {
     Ready,
     Playing,
+    /// Paused with a menu item selected. The board is hidden.
+    Paused(PauseItem),
     Finishing,
     Finished
 }
 
+/// Entries of the pause menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseItem {
+    Resume,
+    Restart,
+    QuitToMenu
+}
+
+impl PauseItem {
+    pub const ALL: [PauseItem; 3] = [PauseItem::Resume, PauseItem::Restart, PauseItem::QuitToMenu];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PauseItem::Resume => "RESUME",
+            PauseItem::Restart => "RESTART",
+            PauseItem::QuitToMenu => "QUIT TO MENU"
+        }
+    }
+
+    fn index(&self) -> usize {
+        PauseItem::ALL.iter().position(|item| item == self).unwrap()
+    }
+
+    pub fn prev(&self) -> PauseItem {
+        PauseItem::ALL[(self.index() + PauseItem::ALL.len() - 1) % PauseItem::ALL.len()]
+    }
+
+    pub fn next(&self) -> PauseItem {
+        PauseItem::ALL[(self.index() + 1) % PauseItem::ALL.len()]
+    }
+}
+
 #[derive(Debug)]
 pub struct Screen {
     pub state: State,
     pub screen_pos: Pos,
-    pub playground: Playground,
+    /// Area of the frame being drawn. Nothing is drawn outside of it.
+    pub area: Rect,
+    pub game: Game,
+    /// Seed given on the command line. Every game reuses it if set.
+    pub seed: Option<u64>,
+    /// Glyph of the ghost piece, if shown.
+    pub ghost: Option<String>,
+    pub keys: KeyMap,
+    pub controller: Controller,
+    /// Key presses that reached the game, without repeats of held keys.
+    pub key_presses: u32,
+    pub records: Records,
+    /// Personal best of the mode before this game.
+    pub best: Option<u64>,
+    /// Whether this game set a new personal best.
+    pub new_best: bool,
     pub debug_msg: String
 }
 
-pub const BG_COLOR: Color = Color::Black;
-pub const SIZE: Size = Size { width: 27, height: 24 };
+// narrowest layout that still fits the score line
+const MIN_WIDTH: u16 = 30;
+// left of the playground frame, right of the hold box
+const PL_X: u16 = 7;
+// width of the column right of the playground
+const SIDE_WIDTH: u16 = 9;
 
 impl Screen {
-    pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>) {
+    pub fn draw(&mut self, f: &mut Frame) {
         // setup pos
-        let f_size = f.size();
+        let f_size = f.area();
         let f_center = Size::new(f_size.width / 2, f_size.height / 2);
-        self.screen_pos = Pos::new(f_center.width - SIZE.mid_x(), f_center.height - SIZE.mid_y());
+        let size = self.size();
+        self.area = f_size;
+        self.screen_pos = Pos::new(
+            f_center.width.saturating_sub(size.mid_x()) as i32, f_center.height.saturating_sub(size.mid_y()) as i32);
 
-        // entire area
-        // let block = Block::default()
-        //     .style(Style::default().bg(Color::Gray));
-        // f.render_widget(block, self.rect(0, 0, SIZE.width, SIZE.height));
+        // score, none in zen
+        let mut score = vec![
+            Span::styled("LEVEL: ",Style::default().add_modifier(Modifier::ITALIC)),
+            Span::styled(format!("{}", self.game.level()),Style::default().add_modifier(Modifier::BOLD)),
+        ];
+        if self.game.settings().mode != Mode::Zen {
+            score.splice(0..0, [
+                Span::styled("SCORE: ",Style::default().add_modifier(Modifier::ITALIC)),
+                Span::styled(format!("{}  ", self.game.score()),Style::default().add_modifier(Modifier::BOLD)),
+            ]);
+        }
+        let score = Paragraph::new(Line::from(score)).alignment(Alignment::Center);
+        f.render_widget(score, self.rect(
+            0, 0, size.width, 1));
 
-        // score
-        let score = Paragraph::new(Spans::from(vec![
-            Span::styled("SCORE: ",Style::default().add_modifier(Modifier::ITALIC)),
-            Span::styled(format!("{}", self.playground.score),Style::default().add_modifier(Modifier::BOLD)),
+        // seed
+        let seed = Paragraph::new(Line::from(vec![
+            Span::styled(format!("SEED: {} ({})", self.game.seed(), self.game.settings().generator),Style::default().fg(Color::DarkGray)),
         ])).alignment(Alignment::Center);
-        f.render_widget(score, self.rect(
-            0, 0, SIZE.width, 1));
+        f.render_widget(seed, self.rect(
+            0, 1, size.width, 1));
 
         // playground frame
-        let pl_size = self.playground.size();
+        let pl_size = self.game.size();
+        let next_x = self.next_x();
+        // the title only fits boards at least as wide as itself
+        let title = if pl_size.width >= 10 { "< TETRIS >" } else { "" };
         let block = Block::default()
             .borders(Borders::ALL)
-            .title("< TETRIS >")
+            .title(title)
             .title_alignment(Alignment::Center)
             .border_type(BorderType::Thick);
-        let Size {width, height} = self.playground.size();
         f.render_widget(block, self.rect(
-            0, 2, pl_size.width + 2, pl_size.height + 2));
+            PL_X, 2, pl_size.width + 2, pl_size.height + 2));
+
+        // no peeking while paused
+        let paused = matches!(self.state, State::Paused(_));
+
+        // playground, without the hidden rows
+        if !paused {
+            let hidden = self.game.hidden_rows() as usize;
+            let widget = Paragraph::new(to_spans(&self.game.board().rows()[hidden..]));
+            f.render_widget(widget, self.rect(
+                PL_X + 1, 3, pl_size.width, pl_size.height));
+        }
 
-        // playground
-        let widget = Paragraph::new(self.playground.buffer.to_spans());
-        f.render_widget(widget, self.rect(
-            1, 3, pl_size.width, pl_size.height));
+        // lines about to clear
+        if let (Phase::LineClear { .. }, false) = (self.game.phase(), paused) {
+            let hidden = self.game.hidden_rows();
+            let board = self.game.board();
+            for row in (0..pl_size.height).filter(|row| board.line_completed(row + hidden)) {
+                let widget = Paragraph::new(Span::styled(
+                    "█".repeat(pl_size.width as usize), Style::default().fg(Color::White)));
+                f.render_widget(widget, self.rect(
+                    PL_X + 1, 3 + row, pl_size.width, 1));
+            }
+        }
+
+        // hold frame
+        if self.game.settings().hold {
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .title(Span::styled("HOLD",Style::default()))
+                .title_alignment(Alignment::Center);
+            f.render_widget(block, self.rect(
+                0, 2, 6, 6));
+        }
+
+        // hold
+        if let Some((hold, used)) = self.game.hold().filter(|_| !paused) {
+            let modifier = if used { Modifier::DIM } else { Modifier::empty() };
+            self.draw_blocks(f, &hold.buffer, 1, 3, "█", modifier);
+        }
 
         // next frame
-        let block = Block::default()
-            .borders(Borders::ALL)
-            .border_type(BorderType::Thick)
-            .title(Span::styled("NEXT",Style::default()))
-            .title_alignment(Alignment::Center);
-        f.render_widget(block, self.rect(
-            1 + pl_size.width + 2 + 1, 2, 6, 6));
+        let next_count = self.game.next_count() as u16;
+        if next_count > 0 {
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .title(Span::styled("NEXT",Style::default()))
+                .title_alignment(Alignment::Center);
+            f.render_widget(block, self.rect(
+                next_x, 2, 6, next_count * 3 + 2));
+        }
 
         // next
-        if let Some(next) = &self.playground.next {
+        for (i, next) in self.game.next().enumerate().filter(|_| !paused) {
             let next_size = next.size();
-            let widget = Paragraph::new(next.buffer.to_spans());
+            let widget = Paragraph::new(to_spans(next.buffer.rows()));
             f.render_widget(widget, self.rect(
-                1 + pl_size.width + 2 + 1 + 1, 3, next_size.width, next_size.height));
+                next_x + 1, 3 + i as u16 * 3, next_size.width, next_size.height));
         }
 
-        // tetro
-        if let Some(tetro) = &self.playground.tetro {
-            let pos = &self.playground.tetro_pos;
-            let size = tetro.size();
-            let widget = Paragraph::new(tetro.buffer.to_spans());
+        // stats
+        let stats_y = if next_count > 0 { 2 + next_count * 3 + 2 + 1 } else { 2 };
+        for (i, (label, value)) in self.stats().into_iter().enumerate() {
+            let widget = Paragraph::new(vec![
+                Line::from(Span::styled(label, Style::default().add_modifier(Modifier::ITALIC))),
+                Line::from(Span::styled(value, Style::default().add_modifier(Modifier::BOLD)))
+            ]);
             f.render_widget(widget, self.rect(
-                1 + pos.x, 3 + pos.y, size.width, size.height));
+                next_x, stats_y + i as u16 * 2, SIDE_WIDTH, 2));
+        }
+
+        // ghost
+        if let (Some(glyph), Some((tetro, pos)), false) = (&self.ghost, self.game.ghost(), paused) {
+            self.draw_tetro(f, tetro, pos, glyph, Modifier::DIM);
+        }
+
+        // tetro
+        if let Some((tetro, pos)) = self.game.tetro().filter(|_| !paused) {
+            self.draw_tetro(f, tetro, pos, "█", Modifier::empty());
         }
 
         match self.state {
             // "PRESS SPACE KEY!"
             State::Ready => {
                 let press_space_key = Paragraph::new(
-                    Spans::from(vec![
+                    Line::from(vec![
                         Span::styled(
                             " PRESS SPACE KEY! ",
                             Style::default().bg(Color::Blue)
@@ -113,14 +229,112 @@ impl Screen {
                     ])
                 ).alignment(Alignment::Center);
                 f.render_widget(press_space_key, self.rect(
-                    0, SIZE.mid_y(), SIZE.width, 1
+                    0, size.mid_y(), size.width, 1
+                ));
+
+                let start_level = Paragraph::new(
+                    Line::from(vec![
+                        Span::styled(
+                            format!(" < START LEVEL {} > ", self.game.settings().start_level),
+                            Style::default().bg(Color::Black)
+                        )
+                    ])
+                ).alignment(Alignment::Center);
+                f.render_widget(start_level, self.rect(
+                    0, size.mid_y() + 2, size.width, 1
+                ));
+            },
+
+            // "PAUSED" and the menu
+            State::Paused(selected) => {
+                let paused = Paragraph::new(
+                    Line::from(vec![
+                        Span::styled(
+                            " PAUSED ",
+                            Style::default().bg(Color::Blue)
+                        )
+                    ])
+                ).alignment(Alignment::Center);
+                f.render_widget(paused, self.rect(
+                    0, size.mid_y() - 2, size.width, 1
+                ));
+
+                for (i, item) in PauseItem::ALL.iter().enumerate() {
+                    let style = if *item == selected {
+                        Style::default().add_modifier(Modifier::REVERSED)
+                    } else {
+                        Style::default()
+                    };
+                    let widget = Paragraph::new(
+                        Line::from(vec![Span::styled(format!(" {} ", item.name()), style)])
+                    ).alignment(Alignment::Center);
+                    f.render_widget(widget, self.rect(
+                        0, size.mid_y() + i as u16, size.width, 1
+                    ));
+                }
+            },
+
+            // results
+            State::Finished if self.game.settings().mode != Mode::Marathon || self.game.is_complete() => {
+                let title = match (self.game.is_complete(), self.game.settings().mode) {
+                    (true, Mode::Ultra) => " TIME UP! ",
+                    (true, _) => " COMPLETE! ",
+                    (false, _) => " FINISHED! "
+                };
+                let seconds = self.game.time().as_secs_f64();
+                let pieces = self.game.pieces();
+                let pps = format!("PPS  {:.2}", if seconds > 0.0 { pieces as f64 / seconds } else { 0.0 });
+                let results = match self.game.settings().mode {
+                    // zen never finishes
+                    Mode::Marathon | Mode::Zen => vec![
+                        format!("SCORE  {}", self.game.score()),
+                        format!("LEVEL  {}", self.game.level()),
+                        format!("LINES  {}", self.game.lines()),
+                        format!("TIME  {}", format_time(self.game.time()))
+                    ],
+                    Mode::Ultra => vec![
+                        format!("SCORE  {}", self.game.score()),
+                        match self.best {
+                            _ if self.new_best => String::from("NEW BEST!"),
+                            Some(best) => format!("BEST  {}", best),
+                            None => String::from("BEST  -")
+                        },
+                        format!("LINES  {}", self.game.lines()),
+                        pps
+                    ],
+                    Mode::Sprint => vec![
+                        format!("TIME  {}", format_time(self.game.time())),
+                        format!("PIECES  {}", pieces),
+                        pps,
+                        format!("KPP  {:.2}", if pieces > 0 { self.key_presses as f64 / pieces as f64 } else { 0.0 })
+                    ],
+                    Mode::Cheese => vec![
+                        format!("TIME  {}", format_time(self.game.time())),
+                        match self.best {
+                            _ if self.new_best => String::from("NEW BEST!"),
+                            Some(best) => format!("BEST  {}", format_time(Duration::from_millis(best))),
+                            None => String::from("BEST  -")
+                        },
+                        format!("PIECES  {}", pieces),
+                        pps
+                    ]
+                };
+
+                let mut lines = vec![Line::from(Span::styled(
+                    title, Style::default().bg(Color::Blue).add_modifier(Modifier::SLOW_BLINK)))];
+                lines.extend(results.into_iter().map(|result| Line::from(Span::styled(
+                    format!(" {} ", result), Style::default().bg(Color::Black)))));
+                let height = lines.len() as u16;
+                let widget = Paragraph::new(lines).alignment(Alignment::Center);
+                f.render_widget(widget, self.rect(
+                    0, size.mid_y() - height / 2, size.width, height
                 ));
             },
 
             // "FINISHED!"
             State::Finished => {
                 let finished = Paragraph::new(
-                    Spans::from(vec![
+                    Line::from(vec![
                         Span::styled(
                             " FINISHED! ",
                             Style::default().bg(Color::Blue)
@@ -129,7 +343,7 @@ impl Screen {
                     ])
                 ).alignment(Alignment::Center);
                 f.render_widget(finished, self.rect(
-                    0, SIZE.mid_y(), SIZE.width, 1
+                    0, size.mid_y(), size.width, 1
                 ));
             },
             _ => {}
@@ -137,13 +351,12 @@ impl Screen {
 
         // debug_msg
         let debug_msg = Paragraph::new(
-            Spans::from(vec![
+            Line::from(vec![
                 Span::raw(self.debug_msg.clone()),
-                Span::raw(self.playground.debug_msg.clone()),
             ])
         ).alignment(Alignment::Left);
         f.render_widget(debug_msg, self.rect(
-            0, SIZE.height, SIZE.width, 1
+            0, size.height, size.width, 1
         ));
     }
 
@@ -152,12 +365,20 @@ impl Screen {
             State::Ready => {
             },
             State::Finishing => {
-                self.state = State::Finished
+                self.state = State::Finished;
+                self.record();
             },
             State::Finished => {
             },
+            State::Paused(_) => {
+            },
             State::Playing => {
-                if !self.playground.on_tick() {
+                // held keys repeat only while a tetro falls, not into the
+                // inputs buffered for the next one
+                if self.game.phase() == Phase::Falling {
+                    self.repeat_held();
+                }
+                if !self.game.step() {
                     self.state = State::Finishing
                 }
             }
@@ -165,43 +386,283 @@ impl Screen {
     }
 
     pub fn on_keydown(&mut self, key: &KeyEvent) {
+        let action = self.keys.action(key);
         match self.state {
-            State::Ready => match key.code {
-                KeyCode::Char(char) if char == ' ' => {
-                    self.state = State::Playing;
-                },
+            State::Ready => match (key.code, action) {
+                (KeyCode::Char(' '), _) => self.state = State::Playing,
+                (KeyCode::Left, _) | (_, Some(Action::MoveLeft)) => self.change_start_level(-1),
+                (KeyCode::Right, _) | (_, Some(Action::MoveRight)) => self.change_start_level(1),
                 _ => {}
             },
             State::Finishing => {
             },
-            State::Finished => match key.code {
-                KeyCode::Char(char) if char == ' ' => {
-                    self.state = State::Ready;
-                    self.playground.clear();
+            State::Finished => if key.code == KeyCode::Char(' ') {
+                self.quit_to_menu();
+            } else if action == Some(Action::Restart) {
+                self.restart();
+            },
+            State::Paused(selected) => match (key.code, action) {
+                (_, Some(Action::Pause)) => self.state = State::Playing,
+                (_, Some(Action::Restart)) => self.restart(),
+                (KeyCode::Up, _) => self.state = State::Paused(selected.prev()),
+                (KeyCode::Down | KeyCode::Tab, _) => self.state = State::Paused(selected.next()),
+                (KeyCode::Enter | KeyCode::Char(' '), _) => match selected {
+                    PauseItem::Resume => self.state = State::Playing,
+                    PauseItem::Restart => self.restart(),
+                    PauseItem::QuitToMenu => self.quit_to_menu()
                 },
                 _ => {}
             },
-            State::Playing => {
-                self.playground.on_keydown(key)
+            State::Playing => match action {
+                Some(Action::Pause) => self.pause(),
+                Some(Action::Restart) => self.restart(),
+                Some(action) => if let Some(input) = action.input() {
+                    if self.controller.on_key(action, key, Instant::now(), self.game.frame()) {
+                        self.game.input(input);
+                        self.key_presses += 1;
+                    }
+                },
+                None => {}
             }
         }
     }
 
-    pub fn new() -> Screen {
-        Screen {
+    /// Pauses a game in play when the terminal loses focus.
+    pub fn on_focus_lost(&mut self) {
+        if self.state == State::Playing {
+            self.pause();
+        }
+    }
+
+    pub fn on_keyup(&mut self, key: &KeyEvent) {
+        self.controller.on_release(key.code);
+    }
+
+    /// Repeats the inputs of held keys for the coming frame.
+    fn repeat_held(&mut self) {
+        let size = self.game.size();
+        let limit = size.width.max(size.height + self.game.hidden_rows()) as usize;
+        let frames_per_row = self.game.settings().frames_per_row(self.game.level());
+        for input in self.controller.update(Instant::now(), self.game.frame(), frames_per_row, limit) {
+            self.game.input(input);
+        }
+    }
+
+    fn pause(&mut self) {
+        self.state = State::Paused(PauseItem::Resume);
+        self.controller.release();
+    }
+
+    /// Starts a new game right away.
+    fn restart(&mut self) {
+        self.new_game();
+        self.state = State::Playing;
+    }
+
+    /// Abandons the game for a new one, waiting for the player to start it.
+    fn quit_to_menu(&mut self) {
+        self.new_game();
+        self.state = State::Ready;
+    }
+
+    /// Picks the start level of the game waiting to be started, up to the
+    /// last level of the gravity table.
+    fn change_start_level(&mut self, by: i32) {
+        let mut settings = self.game.settings().clone();
+        let max_level = settings.gravity.len().max(1) as i32;
+        settings.start_level = (settings.start_level as i32 + by).clamp(1, max_level) as u32;
+        self.game = Game::new(settings, self.game.seed());
+        self.best = self.record_name().and_then(|name| self.records.get(&name));
+    }
+
+    /// Saves the score, or the time of a race, of a completed game if it is
+    /// a personal best.
+    fn record(&mut self) {
+        if let (Some(name), true) = (self.record_name(), self.game.is_complete()) {
+            self.new_best = match self.game.settings().mode {
+                Mode::Cheese => self.records.update_lowest(&name, self.game.time().as_millis() as u64),
+                _ => self.records.update(&name, self.game.score() as u64)
+            };
+            if self.new_best {
+                if let Err(err) = self.records.save() {
+                    self.debug_msg = format!("cannot save records: {}", err);
+                }
+            }
+        }
+    }
+
+    /// Name of the personal best of the mode, if it keeps one. Ultra games
+    /// of different lengths, cheese races of different garbage and games
+    /// of different rules are recorded apart.
+    fn record_name(&self) -> Option<String> {
+        let settings = self.game.settings();
+        let mode = match settings.mode {
+            Mode::Ultra => format!("ultra-{}", settings.ultra_frames),
+            Mode::Cheese => format!("cheese-{}-{}-{}", settings.cheese_lines, settings.cheese_rows, settings.messiness),
+            _ => return None
+        };
+        Some(Records::name(&mode, settings))
+    }
+
+    fn new_game(&mut self) {
+        self.controller.release();
+        self.key_presses = 0;
+        self.best = self.record_name().and_then(|name| self.records.get(&name));
+        self.new_best = false;
+        match self.seed {
+            Some(_) => self.game.reset(),
+            None => self.game.restart(rand::random())
+        }
+    }
+
+    pub fn new(args: &Args) -> Screen {
+        let mut screen = Screen {
             state: State::Ready,
             screen_pos: Pos::new(0, 0),
-            playground: Playground::new(),
+            area: Rect::default(),
+            game: Game::new(args.settings.clone(), args.seed.unwrap_or_else(rand::random)),
+            seed: args.seed,
+            ghost: args.ghost.clone(),
+            keys: args.keys.clone(),
+            controller: Controller::new(args.handling),
+            key_presses: 0,
+            records: Records::load(args.records.clone()),
+            best: None,
+            new_best: false,
             debug_msg: String::from("")
+        };
+        screen.best = screen.record_name().and_then(|name| screen.records.get(&name));
+        screen
+    }
+
+    /// Size of the whole layout, which follows the board size.
+    pub fn size(&self) -> Size {
+        let pl_size = self.game.size();
+        let next_height = self.game.next_count() as u16 * 3 + 2;
+        let stats_height = self.stats().len() as u16 * 2;
+        let side_height = match (next_height, stats_height) {
+            (_, 0) => next_height,
+            (2, _) => stats_height,
+            _ => next_height + 1 + stats_height
+        };
+        Size::new(
+            (self.next_x() + SIDE_WIDTH).max(MIN_WIDTH),
+            2 + (pl_size.height + 2).max(side_height))
+    }
+
+    /// Labelled values shown right of the playground, below NEXT.
+    fn stats(&self) -> Vec<(&'static str, String)> {
+        match self.game.settings().mode {
+            Mode::Marathon if self.game.settings().endless => vec![
+                ("LINES", self.game.lines().to_string())
+            ],
+            Mode::Marathon => vec![
+                ("LINES", format!("{}/{}", self.game.lines(), self.game.settings().marathon_lines()))
+            ],
+            Mode::Sprint => vec![
+                ("TIME", format_time(self.game.time())),
+                ("LINES", format!("{}/{}", self.game.lines(), self.game.settings().sprint_lines))
+            ],
+            Mode::Ultra => vec![
+                ("TIME", format_time(self.game.time_left().unwrap_or_default())),
+                ("BEST", self.best.map_or(String::from("-"), |best| best.to_string()))
+            ],
+            Mode::Cheese => vec![
+                ("TIME", format_time(self.game.time())),
+                ("GARBAGE", format!("{}/{}", self.game.garbage_lines(), self.game.settings().cheese_lines)),
+                ("BEST", self.best.map_or(String::from("-"), |best| format_time(Duration::from_millis(best))))
+            ],
+            Mode::Zen => vec![
+                ("LINES", self.game.lines().to_string()),
+                ("TOP OUTS", self.game.top_outs().to_string())
+            ]
+        }
+    }
+
+    /// Left of the NEXT box.
+    fn next_x(&self) -> u16 {
+        PL_X + 1 + self.game.size().width + 2 + 1
+    }
+
+    /// Draws the blocks of `buffer` at (`x`, `y`), leaving the cells under
+    /// its empty cells untouched.
+    fn draw_blocks(&self, f: &mut Frame, buffer: &Buffer,
+                   x: u16, y: u16, glyph: &str, modifier: Modifier) {
+        let Size { width, height } = buffer.size();
+        for by in 0..height {
+            for bx in 0..width {
+                if let Some(block) = buffer.get(bx, by) {
+                    let style = Style::default().fg(to_color(block)).add_modifier(modifier);
+                    let widget = Paragraph::new(Span::styled(glyph.to_string(), style));
+                    f.render_widget(widget, self.rect(x + bx, y + by, 1, 1));
+                }
+            }
+        }
+    }
+
+    /// Draws a tetro at `pos` on the playground. Blocks still in the hidden
+    /// rows are left out.
+    fn draw_tetro(&self, f: &mut Frame, tetro: &Tetro,
+                  pos: Pos, glyph: &str, modifier: Modifier) {
+        let Size { width, height } = tetro.size();
+        for ty in 0..height {
+            for tx in 0..width {
+                let y = pos.y + ty as i32;
+                if let (Some(block), true) = (tetro.buffer.get(tx, ty), y >= 0) {
+                    let style = Style::default().fg(to_color(block)).add_modifier(modifier);
+                    let widget = Paragraph::new(Span::styled(glyph.to_string(), style));
+                    let x = pos.x + tx as i32;
+                    f.render_widget(widget, self.rect(PL_X + 1 + x as u16, 3 + y as u16, 1, 1));
+                }
+            }
         }
     }
 
     pub fn rect(&self, x: u16, y: u16, width: u16, height: u16) -> Rect {
-        Rect {
-            x: x + self.screen_pos.x,
-            y: y + self.screen_pos.y,
+        let rect = Rect {
+            x: x + self.screen_pos.x as u16,
+            y: y + self.screen_pos.y as u16,
             width,
             height
+        };
+        if rect.intersects(self.area) {
+            rect.intersection(self.area)
+        } else {
+            Rect::new(self.area.x, self.area.y, 0, 0)
         }
     }
 }
+
+/// Formats a time as minutes, seconds and milliseconds, like 1:02.345.
+fn format_time(time: Duration) -> String {
+    let ms = time.as_millis();
+    format!("{}:{:02}.{:03}", ms / 60_000, ms / 1000 % 60, ms % 1000)
+}
+
+fn to_color(block: tetris::Block) -> Color {
+    match block {
+        tetris::Block::Tetro(tetris::Color::Cyan) => Color::Cyan,
+        tetris::Block::Tetro(tetris::Color::Blue) => Color::Blue,
+        tetris::Block::Tetro(tetris::Color::Orange) => Color::LightMagenta,
+        tetris::Block::Tetro(tetris::Color::Yellow) => Color::Yellow,
+        tetris::Block::Tetro(tetris::Color::Green) => Color::Green,
+        tetris::Block::Tetro(tetris::Color::Purple) => Color::Magenta,
+        tetris::Block::Tetro(tetris::Color::Red) => Color::Red,
+        tetris::Block::Garbage => Color::DarkGray
+    }
+}
+
+fn to_spans(rows: &[Vec<Cell>]) -> Vec<Line<'static>> {
+    rows.iter().map(|row| {
+        Line::from(
+            row.iter().map(|cell| {
+                match cell {
+                    // garbage is shaded to stand apart from locked tetros
+                    Some(block @ tetris::Block::Garbage) => Span::styled("▓", Style::default().fg(to_color(*block))),
+                    Some(block) => Span::styled("█", Style::default().fg(to_color(*block))),
+                    None => Span::styled(" ", Style::default())
+                }
+            }).collect::<Vec<Span>>()
+        )
+    }).collect::<Vec<Line>>()
+}
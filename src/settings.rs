@@ -0,0 +1,329 @@
+use std::{fmt::{self, Display, Formatter}, str::FromStr};
+use crate::{randomizer::Generator, scoring::Scoring, units::Size};
+
+/// What restarts the lock delay of a tetro resting on the stack.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LockReset {
+    /// Moves and rotations, up to 15 times per row reached (guideline).
+    #[default]
+    Move,
+    /// Only falling a row (classic).
+    Step,
+    /// Moves and rotations, without limit.
+    Infinity
+}
+
+impl LockReset {
+    pub const ALL: [LockReset; 3] = [LockReset::Move, LockReset::Step, LockReset::Infinity];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LockReset::Move => "move",
+            LockReset::Step => "step",
+            LockReset::Infinity => "infinity"
+        }
+    }
+}
+
+impl Display for LockReset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for LockReset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        LockReset::ALL.iter()
+            .find(|reset| reset.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown lock reset: {}", s))
+    }
+}
+
+/// What a game is played for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Clear every level up to [`Settings::marathon_level`], or play until
+    /// top out if [`Settings::endless`].
+    #[default]
+    Marathon,
+    /// Clear [`Settings::sprint_lines`] lines as fast as possible.
+    Sprint,
+    /// Score as much as possible in [`Settings::ultra_frames`].
+    Ultra,
+    /// Dig through [`Settings::cheese_lines`] lines of garbage as fast as
+    /// possible.
+    Cheese,
+    /// Play without end or score at the start level. A top out clears the
+    /// stack as set by [`Settings::zen_clear`] instead of ending the game,
+    /// and takes away a line for every visible row it cleared.
+    Zen
+}
+
+impl Mode {
+    pub const ALL: [Mode; 5] = [Mode::Marathon, Mode::Sprint, Mode::Ultra, Mode::Cheese, Mode::Zen];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Marathon => "marathon",
+            Mode::Sprint => "sprint",
+            Mode::Ultra => "ultra",
+            Mode::Cheese => "cheese",
+            Mode::Zen => "zen"
+        }
+    }
+}
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Mode::ALL.iter()
+            .find(|mode| mode.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown mode: {}", s))
+    }
+}
+
+/// What a top out clears in [`Mode::Zen`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ZenClear {
+    /// The hidden rows and the top [`Settings::zen_clear_rows`] visible rows.
+    #[default]
+    Top,
+    /// The whole board.
+    Board
+}
+
+impl ZenClear {
+    pub const ALL: [ZenClear; 2] = [ZenClear::Top, ZenClear::Board];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ZenClear::Top => "top",
+            ZenClear::Board => "board"
+        }
+    }
+}
+
+impl Display for ZenClear {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ZenClear {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ZenClear::ALL.iter()
+            .find(|clear| clear.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown zen clear: {}", s))
+    }
+}
+
+/// Built-in sets of [`Settings`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rules {
+    /// Guideline rules: hold, 5 next, lock delay, no entry delay.
+    #[default]
+    Modern,
+    /// NES-like rules: no hold, 1 next, no lock delay, entry and line clear
+    /// delays and NES gravity and scoring.
+    Classic
+}
+
+impl Rules {
+    pub const ALL: [Rules; 2] = [Rules::Modern, Rules::Classic];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rules::Modern => "modern",
+            Rules::Classic => "classic"
+        }
+    }
+
+    pub fn settings(&self) -> Settings {
+        match self {
+            Rules::Modern => Settings::default(),
+            Rules::Classic => Settings {
+                generator: Generator::Nes,
+                hold: false,
+                next_count: 1,
+                lock_delay: 1,
+                lock_reset: LockReset::Step,
+                are: 10,
+                line_clear_delay: 20,
+                gravity: Settings::nes_gravity(),
+                scoring: Scoring::Nes,
+                ..Settings::default()
+            }
+        }
+    }
+}
+
+impl Display for Rules {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Rules {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rules::ALL.iter()
+            .find(|rules| rules.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown rules: {}", s))
+    }
+}
+
+/// Rules a game is played with. Recorded alongside the seed so a game can
+/// be reproduced.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub mode: Mode,
+    /// Last level of a [`Mode::Marathon`], 15 in the guideline. The level
+    /// stays there once the goal is reached.
+    pub marathon_level: u32,
+    /// Whether a marathon goes on past its goal.
+    pub endless: bool,
+    /// Lines to clear in [`Mode::Sprint`].
+    pub sprint_lines: u32,
+    /// Length of a [`Mode::Ultra`] game in frames.
+    pub ultra_frames: u64,
+    /// Garbage lines to clear in [`Mode::Cheese`].
+    pub cheese_lines: u32,
+    /// Garbage rows kept on the board until the rest of the
+    /// [`Settings::cheese_lines`] fit.
+    pub cheese_rows: u16,
+    /// Chance in percent that the hole of a garbage row is placed at random
+    /// rather than under the hole of the row above. 0 lines all holes up.
+    pub messiness: u32,
+    pub zen_clear: ZenClear,
+    /// Visible rows cleared by [`ZenClear::Top`].
+    pub zen_clear_rows: u16,
+    /// Board size in cells, at least [`Settings::MIN_WIDTH`] by
+    /// [`Settings::MIN_HEIGHT`].
+    pub width: u16,
+    pub height: u16,
+    /// Rows above the visible field that tetros spawn into, at least
+    /// [`Settings::MIN_HIDDEN_ROWS`].
+    pub hidden_rows: u16,
+    /// Whether locking a tetro partly above the visible field ends the game.
+    /// Otherwise only a tetro locked entirely above it does.
+    pub partial_lock_out: bool,
+    pub generator: Generator,
+    /// Whether the hold slot can be used.
+    pub hold: bool,
+    /// Number of upcoming tetros shown, up to [`Settings::MAX_NEXT`].
+    pub next_count: usize,
+    /// Frames a tetro may rest on the stack before it locks.
+    pub lock_delay: u32,
+    pub lock_reset: LockReset,
+    /// Entry delay: frames between a lock, or the end of its line clear,
+    /// and the next spawn.
+    pub are: u32,
+    /// Frames completed lines stay on the board before they are removed.
+    pub line_clear_delay: u32,
+    pub start_level: u32,
+    /// Lines to clear for each level up.
+    pub lines_per_level: u32,
+    /// Frames per row of gravity, starting at level 1. Values below 1 drop
+    /// several rows a frame. Levels past the end use the last entry.
+    pub gravity: Vec<f64>,
+    pub scoring: Scoring
+}
+
+impl Settings {
+    pub const MAX_NEXT: usize = 6;
+    pub const MIN_WIDTH: u16 = 4;
+    pub const MIN_HEIGHT: u16 = 4;
+    pub const MIN_HIDDEN_ROWS: u16 = 2;
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width.max(Settings::MIN_WIDTH), self.height.max(Settings::MIN_HEIGHT))
+    }
+
+    pub fn hidden_rows(&self) -> u16 {
+        self.hidden_rows.max(Settings::MIN_HIDDEN_ROWS)
+    }
+
+    /// Size of the board including the hidden rows.
+    pub fn board_size(&self) -> Size {
+        let size = self.size();
+        Size::new(size.width, size.height + self.hidden_rows())
+    }
+
+    /// Guideline gravity curve for levels 1 to 20.
+    pub fn guideline_gravity() -> Vec<f64> {
+        (0..20).map(|i| {
+            let level = i as f64;
+            60.0 * (0.8 - level * 0.007).powf(level)
+        }).collect()
+    }
+
+    /// NES gravity for levels 0 to 29, played as levels 1 to 30.
+    pub fn nes_gravity() -> Vec<f64> {
+        let mut gravity = vec![48.0, 43.0, 38.0, 33.0, 28.0, 23.0, 18.0, 13.0, 8.0, 6.0,
+                               5.0, 5.0, 5.0, 4.0, 4.0, 4.0, 3.0, 3.0, 3.0];
+        gravity.extend([2.0; 10]);
+        gravity.push(1.0);
+        gravity
+    }
+
+    /// Lines to clear in a [`Mode::Marathon`]: those of every level from the
+    /// start level to [`Settings::marathon_level`], or of the start level
+    /// alone if it is past that.
+    pub fn marathon_lines(&self) -> u32 {
+        let levels = self.marathon_level.saturating_sub(self.start_level) + 1;
+        levels * self.lines_per_level.max(1)
+    }
+
+    pub fn frames_per_row(&self, level: u32) -> f64 {
+        let i = (level.max(1) - 1) as usize;
+        self.gravity.get(i).or(self.gravity.last()).copied().unwrap_or(60.0)
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            mode: Mode::default(),
+            marathon_level: 15,
+            endless: false,
+            sprint_lines: 40,
+            ultra_frames: 2 * 60 * 60,
+            cheese_lines: 18,
+            cheese_rows: 10,
+            messiness: 100,
+            zen_clear: ZenClear::default(),
+            zen_clear_rows: 10,
+            width: 10,
+            height: 20,
+            hidden_rows: 20,
+            partial_lock_out: false,
+            generator: Generator::default(),
+            hold: true,
+            next_count: 5,
+            lock_delay: 30,
+            lock_reset: LockReset::default(),
+            are: 0,
+            line_clear_delay: 0,
+            start_level: 1,
+            lines_per_level: 10,
+            gravity: Settings::guideline_gravity(),
+            scoring: Scoring::default()
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Super Rotation System tables.
+//!
+//! Tetros are stored trimmed to their blocks, while SRS rotates them inside a
+//! fixed 3x3 (4x4 for I) box. [`offset`] gives where the trimmed shape sits
+//! in that box, and [`kicks`] the positions tried in order for a rotation.
+//! Kicks are `(x, y)` with y pointing up, as in the guideline tables.
+//!
+//! SRS has no 180 rotation, so the 180 kicks follow the SRS+ table used by
+//! TETR.IO for every tetro but O.
+
+use crate::tetro::{Kind, Orientation};
+
+type Kicks = [(i32, i32); 5];
+
+const JLSTZ_0_R: Kicks = [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)];
+const JLSTZ_R_0: Kicks = [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)];
+const JLSTZ_R_2: Kicks = [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)];
+const JLSTZ_2_R: Kicks = [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)];
+const JLSTZ_2_L: Kicks = [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)];
+const JLSTZ_L_2: Kicks = [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)];
+const JLSTZ_L_0: Kicks = [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)];
+const JLSTZ_0_L: Kicks = [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)];
+
+const I_0_R: Kicks = [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)];
+const I_R_0: Kicks = [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)];
+const I_R_2: Kicks = [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)];
+const I_2_R: Kicks = [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)];
+const I_2_L: Kicks = [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)];
+const I_L_2: Kicks = [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)];
+const I_L_0: Kicks = [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)];
+const I_0_L: Kicks = [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)];
+
+type Kicks180 = [(i32, i32); 6];
+
+const FLIP_0_2: Kicks180 = [(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)];
+const FLIP_2_0: Kicks180 = [(0, 0), (0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)];
+const FLIP_R_L: Kicks180 = [(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)];
+const FLIP_L_R: Kicks180 = [(0, 0), (-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)];
+
+/// Position of the trimmed tetro inside its rotation box.
+pub fn offset(kind: Kind, orientation: Orientation) -> (i32, i32) {
+    match (kind, orientation) {
+        (Kind::O, _) => (0, 0),
+        (Kind::I, Orientation::Zero) => (0, 1),
+        (Kind::I, Orientation::Right) => (2, 0),
+        (Kind::I, Orientation::Two) => (0, 2),
+        (Kind::I, Orientation::Left) => (1, 0),
+        (_, Orientation::Zero) => (0, 0),
+        (_, Orientation::Right) => (1, 0),
+        (_, Orientation::Two) => (0, 1),
+        (_, Orientation::Left) => (0, 0)
+    }
+}
+
+/// Kick offsets to try, in order, when rotating from `from` to `to`.
+pub fn kicks(kind: Kind, from: Orientation, to: Orientation) -> &'static [(i32, i32)] {
+    use Orientation::*;
+
+    match kind {
+        Kind::O => &[(0, 0)],
+        _ if from.flip() == to => match from {
+            Zero => &FLIP_0_2,
+            Two => &FLIP_2_0,
+            Right => &FLIP_R_L,
+            Left => &FLIP_L_R
+        },
+        Kind::I => match (from, to) {
+            (Zero, Right) => &I_0_R,
+            (Right, Zero) => &I_R_0,
+            (Right, Two) => &I_R_2,
+            (Two, Right) => &I_2_R,
+            (Two, Left) => &I_2_L,
+            (Left, Two) => &I_L_2,
+            (Left, Zero) => &I_L_0,
+            (Zero, Left) => &I_0_L,
+            _ => &[(0, 0)]
+        },
+        _ => match (from, to) {
+            (Zero, Right) => &JLSTZ_0_R,
+            (Right, Zero) => &JLSTZ_R_0,
+            (Right, Two) => &JLSTZ_R_2,
+            (Two, Right) => &JLSTZ_2_R,
+            (Two, Left) => &JLSTZ_2_L,
+            (Left, Two) => &JLSTZ_L_2,
+            (Left, Zero) => &JLSTZ_L_0,
+            (Zero, Left) => &JLSTZ_0_L,
+            _ => &[(0, 0)]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quarter_turn_kicks_undo_each_other() {
+        use Orientation::*;
+        for kind in [Kind::I, Kind::J, Kind::L, Kind::S, Kind::T, Kind::Z] {
+            for from in [Zero, Right, Two, Left] {
+                for to in [from.cw(), from.ccw()] {
+                    let undone = kicks(kind, to, from).iter().map(|(x, y)| (-x, -y));
+                    assert!(kicks(kind, from, to).iter().copied().eq(undone), "{:?} {:?} {:?}", kind, from, to);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn every_rotation_tries_in_place_first() {
+        use Orientation::*;
+        for kind in Kind::ALL {
+            for from in [Zero, Right, Two, Left] {
+                for to in [from.cw(), from.ccw(), from.flip()] {
+                    assert_eq!(kicks(kind, from, to)[0], (0, 0));
+                }
+            }
+        }
+    }
+}
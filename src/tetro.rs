@@ -1,98 +1,153 @@
-use tui::{
-    backend::Backend,
-    terminal::{Terminal, Frame},
-    backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Style, Modifier},
-    text::{Span, Spans},
-    widgets::{Borders, BorderType, Block, Paragraph},
-};
-use rand::{Rng, thread_rng};
-use tui::widgets::Widget;
 use crate::{
-    buffer::Buffer,
+    buffer::{Block, Buffer, Color},
     units::Size
 };
 
-#[derive(Clone, Debug)]
-pub enum TetroState {
-    Descent,
-    WaitToPlace
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Kind {
+    I,
+    J,
+    L,
+    O,
+    S,
+    T,
+    Z
+}
+
+impl Kind {
+    pub const ALL: [Kind; 7] = [Kind::I, Kind::J, Kind::L, Kind::O, Kind::S, Kind::T, Kind::Z];
+
+    pub fn color(&self) -> Color {
+        match self {
+            Kind::I => Color::Cyan,
+            Kind::J => Color::Blue,
+            Kind::L => Color::Orange,
+            Kind::O => Color::Yellow,
+            Kind::S => Color::Green,
+            Kind::T => Color::Purple,
+            Kind::Z => Color::Red
+        }
+    }
+}
+
+/// Rotation state, named as in SRS: spawn (0), clockwise (R), 180 (2) and
+/// counter-clockwise (L).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    Zero,
+    Right,
+    Two,
+    Left
+}
+
+impl Orientation {
+    pub fn cw(&self) -> Orientation {
+        match self {
+            Orientation::Zero => Orientation::Right,
+            Orientation::Right => Orientation::Two,
+            Orientation::Two => Orientation::Left,
+            Orientation::Left => Orientation::Zero
+        }
+    }
+
+    pub fn ccw(&self) -> Orientation {
+        match self {
+            Orientation::Zero => Orientation::Left,
+            Orientation::Right => Orientation::Zero,
+            Orientation::Two => Orientation::Right,
+            Orientation::Left => Orientation::Two
+        }
+    }
+
+    pub fn flip(&self) -> Orientation {
+        self.cw().cw()
+    }
+
+    fn index(&self) -> u8 {
+        match self {
+            Orientation::Zero => 0,
+            Orientation::Right => 1,
+            Orientation::Two => 2,
+            Orientation::Left => 3
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Tetro {
-    pub state: TetroState,
+    pub kind: Kind,
+    pub orientation: Orientation,
     pub buffer: Buffer
 }
 
 impl Tetro {
-    pub fn rotate_right(&mut self) -> Tetro {
-        Tetro {
-            state: TetroState::Descent,
-            buffer: self.buffer.rotate_right()
-        }
+    pub fn rotate_right(&self) -> Tetro {
+        self.rotated(self.orientation.cw())
     }
 
-    pub fn size(&self) -> Size {
-        self.buffer.size()
+    pub fn rotate_left(&self) -> Tetro {
+        self.rotated(self.orientation.ccw())
     }
 
-    pub fn new() -> Tetro {
-        let mut tetro = Tetro::tetros();
+    pub fn rotate_180(&self) -> Tetro {
+        self.rotated(self.orientation.flip())
+    }
 
-        let mut rng = thread_rng();
-        let mut rotate = rng.gen_range(0..4);
-        while rotate >= 0 {
-            tetro.rotate_right();
-            rotate -= 1;
+    /// The same tetro turned to `orientation`.
+    pub fn rotated(&self, orientation: Orientation) -> Tetro {
+        let turns = (4 + orientation.index() - self.orientation.index()) % 4;
+        let mut buffer = self.buffer.clone();
+        for _ in 0..turns {
+            buffer = buffer.rotate_right();
         }
 
-        tetro
-    }
-
-    pub fn from_vec(buffer: Vec<Vec<Option<Color>>>) -> Tetro {
         Tetro {
-            state: TetroState::Descent,
-            buffer: Buffer::from_vecs(buffer)
+            kind: self.kind,
+            orientation,
+            buffer
         }
     }
 
-    fn tetros() -> Tetro {
-        const TETRO_LEN: usize = 7;
-
-        let fns: [fn() -> Tetro; TETRO_LEN] = [
-            || -> Tetro { Tetro::from_vec(vec![
-                vec![Some(Color::Cyan), Some(Color::Cyan), Some(Color::Cyan), Some(Color::Cyan)],
-            ])},
-            || -> Tetro { Tetro::from_vec(vec![
-                vec![Some(Color::Blue), None, None],
-                vec![Some(Color::Blue), Some(Color::Blue), Some(Color::Blue)]
-            ])},
-            || -> Tetro { Tetro::from_vec(vec![
-                vec![None, None, Some(Color::LightMagenta)],
-                vec![Some(Color::LightMagenta), Some(Color::LightMagenta), Some(Color::LightMagenta)]
-            ])},
-            || -> Tetro { Tetro::from_vec(vec![
-                vec![Some(Color::Yellow), Some(Color::Yellow)],
-                vec![Some(Color::Yellow), Some(Color::Yellow)]
-            ])},
-            || -> Tetro { Tetro::from_vec(vec![
-                vec![None, Some(Color::Green), Some(Color::Green)],
-                vec![Some(Color::Green), Some(Color::Green), None]
-            ])},
-            || -> Tetro { Tetro::from_vec(vec![
-                vec![None, Some(Color::Magenta), None],
-                vec![Some(Color::Magenta), Some(Color::Magenta), Some(Color::Magenta)]
-            ])},
-            || -> Tetro { Tetro::from_vec(vec![
-                vec![Some(Color::Red), Some(Color::Red), None],
-                vec![None, Some(Color::Red), Some(Color::Red)]
-            ])}
-        ];
-
-        let mut rng = thread_rng();
-        let i = rng.gen_range(0..TETRO_LEN);
-        fns[i]()
+    pub fn size(&self) -> Size {
+        self.buffer.size()
+    }
+
+    pub fn new(kind: Kind) -> Tetro {
+        let color = Some(Block::Tetro(kind.color()));
+        let cells = match kind {
+            Kind::I => vec![
+                vec![color, color, color, color],
+            ],
+            Kind::J => vec![
+                vec![color, None, None],
+                vec![color, color, color]
+            ],
+            Kind::L => vec![
+                vec![None, None, color],
+                vec![color, color, color]
+            ],
+            Kind::O => vec![
+                vec![color, color],
+                vec![color, color]
+            ],
+            Kind::S => vec![
+                vec![None, color, color],
+                vec![color, color, None]
+            ],
+            Kind::T => vec![
+                vec![None, color, None],
+                vec![color, color, color]
+            ],
+            Kind::Z => vec![
+                vec![color, color, None],
+                vec![None, color, color]
+            ]
+        };
+
+        Tetro {
+            kind,
+            orientation: Orientation::Zero,
+            buffer: Buffer::from_vecs(cells)
+        }
     }
 }
@@ -1,28 +1,18 @@
-use tui::{
-    backend::Backend,
-    terminal::{Terminal, Frame},
-    backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Style, Modifier},
-    text::{Span, Spans},
-    widgets::{Borders, BorderType, Block, Paragraph},
-};
-use rand::{Rng, thread_rng};
-use tui::widgets::Widget;
-
-#[derive(Debug)]
+/// Position of a tetro on the board. Rows above the visible field are
+/// negative.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Pos {
-  pub x: u16,
-  pub y: u16
+  pub x: i32,
+  pub y: i32
 }
 
 impl Pos {
-    pub fn new(x: u16, y: u16) -> Pos {
+    pub fn new(x: i32, y: i32) -> Pos {
         Pos { x, y }
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Size {
   pub width: u16,
   pub height: u16